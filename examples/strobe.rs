@@ -1,15 +1,12 @@
-use std::thread;
+use std::ops::ControlFlow;
 use std::time::Duration;
 
-use rpi_ws281x::Led;
+use rpi_ws281x::{Led, RenderLoop};
 
 const INTERVAL: Duration = Duration::from_millis(300);
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let led_count: u16 = 19;
-
-    let on = vec![Led::ON; led_count as usize];
-    let off = vec![Led::OFF; led_count as usize];
+    let led_count: u32 = 19;
 
     let mut strip = rpi_ws281x::Controller::builder(10)
         .channel(
@@ -19,11 +16,14 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         )
         .build()?;
 
-    loop {
-        strip.render_buffer([&on[..], &[]])?;
-        thread::sleep(INTERVAL);
-
-        strip.render_buffer([&off[..], &[]])?;
-        thread::sleep(INTERVAL);
-    }
+    RenderLoop::new(1.0 / INTERVAL.as_secs_f64()).run(&mut strip, |[leds, _], frame| {
+        let led = if frame.index() % 2 == 0 {
+            Led::ON
+        } else {
+            Led::OFF
+        };
+        leds.iter_mut().for_each(|l| *l = led);
+        ControlFlow::Continue(())
+    })?;
+    Ok(())
 }
@@ -0,0 +1,320 @@
+use crate::driver_thread;
+use crate::{Error, Led, LedDriver, Result, NUM_CHANNELS};
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc;
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+/// An async wrapper around an [`LedDriver`], for use with tokio or any other async runtime.
+///
+/// Rendering is a blocking call into the C library. This wrapper runs the driver on a dedicated
+/// thread, so awaiting a render never blocks the async executor. The driver is created on that
+/// thread and never leaves it, so it does not have to be [`Send`].
+///
+/// Dropping the controller stops the render thread once it has finished the frame in progress,
+/// without waiting for it. Use [`AsyncController::shutdown`] to wait until it has stopped.
+///
+/// Requires the `async` cargo feature. Unlike the rest of the crate, it needs Rust 1.71 or
+/// newer, unless tokio is pinned to a release that supports an older toolchain.
+///
+/// # Example
+///
+/// ```no_run
+/// # use rpi_ws281x::{AsyncController, Channel, Controller, Led};
+/// # async fn run() -> rpi_ws281x::Result<()> {
+/// let mut controller = AsyncController::spawn(|| {
+///     Controller::builder(10)
+///         .channel(Channel::builder(18, 60).build())
+///         .build()
+/// })
+/// .await?;
+///
+/// loop {
+///     controller.buffer(0).fill(Led::RED);
+///     controller.render().await?;
+/// }
+/// # }
+/// ```
+pub struct AsyncController {
+    commands: Option<mpsc::Sender<Render>>,
+    buffers: [Vec<Led>; NUM_CHANNELS],
+    /// Buffers that are sent to the render thread and returned after rendering, so no new ones
+    /// have to be allocated every frame.
+    spare: Option<[Vec<Led>; NUM_CHANNELS]>,
+    brightness: [u8; NUM_CHANNELS],
+    min_frame_interval: Duration,
+    /// Resolves once the render thread is about to exit, with the panic that made it stop, if
+    /// any. Waiting on it never blocks the executor, unlike joining the thread.
+    stopped: Option<oneshot::Receiver<Option<Panic>>>,
+}
+
+type Panic = Box<dyn Any + Send + 'static>;
+
+struct Render {
+    buffers: [Vec<Led>; NUM_CHANNELS],
+    brightness: [u8; NUM_CHANNELS],
+    reply: oneshot::Sender<(Result<()>, [Vec<Led>; NUM_CHANNELS])>,
+}
+
+impl AsyncController {
+    /// Spawns the render thread and creates the driver on it by calling `init`.
+    /// Resolves to the error from `init`, if it fails.
+    pub async fn spawn<D, F>(init: F) -> Result<Self>
+    where
+        D: LedDriver,
+        F: FnOnce() -> Result<D> + Send + 'static,
+    {
+        let (info_tx, info_rx) = oneshot::channel();
+        let (commands, command_rx) = mpsc::channel();
+        let (stopped_tx, stopped) = oneshot::channel();
+        let thread = driver_thread::spawn(
+            init,
+            move |info| info_tx.send(info).ok(),
+            move |mut driver, ()| {
+                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                    render_thread(&mut driver, command_rx)
+                }));
+                drop(driver);
+                let _ = stopped_tx.send(result.err());
+                Ok(())
+            },
+        );
+
+        match info_rx.await {
+            Ok(Ok(info)) => Ok(Self {
+                commands: Some(commands),
+                buffers: [
+                    vec![Led::OFF; info.channel_lens[0]],
+                    vec![Led::OFF; info.channel_lens[1]],
+                ],
+                spare: None,
+                brightness: info.brightness,
+                min_frame_interval: info.min_frame_interval,
+                stopped: Some(stopped),
+            }),
+            Ok(Err(error)) => Err(error),
+            Err(_) => driver_thread::resume_panic(thread),
+        }
+    }
+
+    /// Returns a mutable slice where all the LED values can be set directly. The values are
+    /// rendered on the next call to [`AsyncController::render`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel_index >= NUM_CHANNELS`.
+    pub fn buffer(&mut self, channel_index: usize) -> &mut [Led] {
+        &mut self.buffers[channel_index]
+    }
+
+    /// Returns mutable slices where the LED values of all channels can be set directly.
+    pub fn buffers(&mut self) -> [&mut [Led]; NUM_CHANNELS] {
+        let [channel0, channel1] = &mut self.buffers;
+        [channel0, channel1]
+    }
+
+    /// Returns the number of LEDs on the channel with the given index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel_index >= NUM_CHANNELS`.
+    pub fn channel_len(&self, channel_index: usize) -> usize {
+        self.buffers[channel_index].len()
+    }
+
+    /// Returns the minimum time between two frames. See [`Controller::min_frame_interval`].
+    ///
+    /// [`Controller::min_frame_interval`]: crate::Controller::min_frame_interval
+    pub fn min_frame_interval(&self) -> Duration {
+        self.min_frame_interval
+    }
+
+    /// Returns the brightness of the channel with the given index, between 0 and 255.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel_index >= NUM_CHANNELS`.
+    pub fn brightness(&self, channel_index: usize) -> u8 {
+        self.brightness[channel_index]
+    }
+
+    /// Sets the brightness of the channel with the given index, between 0 and 255.
+    /// Takes effect on the next render.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel_index >= NUM_CHANNELS`.
+    pub fn set_brightness(&mut self, channel_index: usize, brightness: u8) {
+        self.brightness[channel_index] = brightness;
+    }
+
+    /// Renders what is currently in the buffers. Resolves once the frame has been completely
+    /// sent out to the LEDs.
+    ///
+    /// The buffers are copied before rendering starts, so they can be modified again as soon
+    /// as this future is created. If the future is dropped before it resolves, the frame might
+    /// still be rendered.
+    pub async fn render(&mut self) -> Result<()> {
+        let mut frame = match self.spare.take() {
+            Some(frame) => frame,
+            None => [
+                vec![Led::OFF; self.buffers[0].len()],
+                vec![Led::OFF; self.buffers[1].len()],
+            ],
+        };
+        for (spare, buffer) in frame.iter_mut().zip(&self.buffers) {
+            spare.copy_from_slice(buffer);
+        }
+        self.send(frame).await
+    }
+
+    /// Renders the given buffers, leaving the buffers held by this instance untouched.
+    /// Resolves once the frame has been completely sent out to the LEDs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the `&[Led]` slices are not the same length as the corresponding channel.
+    pub async fn render_buffer(&mut self, buffers: [&[Led]; NUM_CHANNELS]) -> Result<()> {
+        assert_eq!(self.buffers[0].len(), buffers[0].len());
+        assert_eq!(self.buffers[1].len(), buffers[1].len());
+        self.send([buffers[0].to_vec(), buffers[1].to_vec()]).await
+    }
+
+    async fn send(&mut self, buffers: [Vec<Led>; NUM_CHANNELS]) -> Result<()> {
+        let (reply, reply_rx) = oneshot::channel();
+        let command = Render {
+            buffers,
+            brightness: self.brightness,
+            reply,
+        };
+        let sent = match &self.commands {
+            Some(commands) => commands.send(command).is_ok(),
+            None => return Err(Error::RenderThreadStopped),
+        };
+        if !sent {
+            return Err(self.join().await);
+        }
+        match reply_rx.await {
+            Ok((result, buffers)) => {
+                self.spare = Some(buffers);
+                result
+            }
+            Err(_) => Err(self.join().await),
+        }
+    }
+
+    /// Stops the render thread once it has finished the frame in progress, and resolves when
+    /// it has exited.
+    ///
+    /// # Panics
+    ///
+    /// Propagates the panic if the render thread has panicked.
+    pub async fn shutdown(mut self) {
+        self.join().await;
+    }
+
+    /// Closes the command channel, which makes the render thread exit, and waits for it.
+    /// Propagates its panic. All later renders fail with [`Error::RenderThreadStopped`].
+    async fn join(&mut self) -> Error {
+        self.commands = None;
+        if let Some(stopped) = self.stopped.take() {
+            if let Ok(Some(panic)) = stopped.await {
+                panic::resume_unwind(panic);
+            }
+        }
+        Error::RenderThreadStopped
+    }
+}
+
+fn render_thread(driver: &mut impl LedDriver, commands: mpsc::Receiver<Render>) {
+    for render in commands {
+        for (channel_index, &brightness) in render.brightness.iter().enumerate() {
+            driver.set_brightness(channel_index, brightness);
+        }
+        let [channel0, channel1] = &render.buffers;
+        let result = driver
+            .render_buffer([channel0, channel1])
+            .and_then(|()| driver.wait());
+        let _ = render.reply.send((result, render.buffers));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AsyncController;
+    use crate::driver_thread::test_driver::Forward;
+    use crate::{Error, Led, SimulatedController};
+    use std::sync::mpsc;
+
+    #[tokio::test]
+    async fn render() {
+        let (tx, frames) = mpsc::channel();
+        let mut controller = AsyncController::spawn(move || Ok(Forward::new([2, 1], tx)))
+            .await
+            .unwrap();
+        assert_eq!(controller.channel_len(0), 2);
+        assert_eq!(controller.channel_len(1), 1);
+
+        controller.buffer(0)[1] = Led::RED;
+        controller.set_brightness(0, 30);
+        controller.render().await.unwrap();
+        // The frame has been rendered when the future resolves.
+        assert_eq!(frames.try_recv().unwrap(), (vec![Led::OFF, Led::RED], 30));
+
+        controller.render().await.unwrap();
+        assert_eq!(frames.try_recv().unwrap(), (vec![Led::OFF, Led::RED], 30));
+
+        controller
+            .render_buffer([&[Led::BLUE; 2], &[Led::OFF]])
+            .await
+            .unwrap();
+        assert_eq!(frames.try_recv().unwrap(), (vec![Led::BLUE; 2], 30));
+        assert_eq!(controller.buffer(0), &[Led::OFF, Led::RED]);
+
+        controller.buffer(0)[0] = Led::WHITE;
+        assert!(matches!(controller.render().await, Err(Error::SpiTransfer)));
+    }
+
+    #[tokio::test]
+    async fn shutdown() {
+        let (tx, frames) = mpsc::channel();
+        let controller = AsyncController::spawn(move || Ok(Forward::new([1, 0], tx)))
+            .await
+            .unwrap();
+        controller.shutdown().await;
+        // The driver is dropped together with the render thread.
+        assert_eq!(frames.try_recv(), Err(mpsc::TryRecvError::Disconnected));
+    }
+
+    #[tokio::test]
+    async fn render_thread_panic_is_propagated() {
+        let (tx, frames) = mpsc::channel();
+        let mut controller = AsyncController::spawn(move || Ok(Forward::new([1, 0], tx)))
+            .await
+            .unwrap();
+        // Makes the test driver panic when sending the frame.
+        drop(frames);
+        let render = tokio::spawn(async move { controller.render().await });
+        assert!(render.await.unwrap_err().is_panic());
+    }
+
+    #[tokio::test]
+    async fn futures_are_send() {
+        fn assert_send<T: Send>(_: &T) {}
+        let (tx, _frames) = mpsc::channel();
+        let mut controller = AsyncController::spawn(move || Ok(Forward::new([1, 0], tx)))
+            .await
+            .unwrap();
+        assert_send(&controller.render());
+        assert_send(&controller.render_buffer([&[Led::OFF], &[]]));
+        assert_send(&controller.shutdown());
+    }
+
+    #[tokio::test]
+    async fn init_error() {
+        let result =
+            AsyncController::spawn(|| Err::<SimulatedController, _>(Error::MailboxDevice)).await;
+        assert!(matches!(result, Err(Error::MailboxDevice)));
+    }
+}
@@ -0,0 +1,236 @@
+use crate::Led;
+
+/// Ways of combining two [`Led`] colors. See [`Led::blend`].
+///
+/// All modes operate on each of the four channels separately.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BlendMode {
+    /// Draws the top color over the bottom one with the given opacity, between 0.0 (only the
+    /// bottom color is visible) and 1.0 (only the top color is visible).
+    Alpha(f32),
+    /// Multiplies the colors. The result is never brighter than either input.
+    /// Blending with [`Led::ON`] has no effect.
+    Multiply,
+    /// The inverse of multiplying the inverted colors. The result is never darker than either
+    /// input. Blending with [`Led::OFF`] has no effect.
+    Screen,
+    /// Takes the maximum of each channel.
+    Max,
+    /// Subtracts the top color from the bottom color, saturating at zero.
+    Subtract,
+    /// The absolute difference between the colors.
+    Difference,
+}
+
+impl Led {
+    /// Linearly interpolates between `a` and `b`. `t` is clamped to `0.0..=1.0`, where 0.0
+    /// gives `a` and 1.0 gives `b`.
+    ///
+    /// This works directly on the channel values. It is fast, but since the values are not
+    /// linear in light intensity, the midpoint between two saturated colors comes out too dark.
+    /// See [`Led::lerp_linear`] and [`Led::lerp_oklab`] for better looking crossfades.
+    pub fn lerp(a: Led, b: Led, t: f32) -> Led {
+        let t = t.clamp(0.0, 1.0);
+        a.zip_with(b, |a, b| {
+            let (a, b) = (f32::from(a), f32::from(b));
+            (a + (b - a) * t).round() as u8
+        })
+    }
+
+    /// Interpolates between `a` and `b` in linear light. The channel values are treated as sRGB
+    /// encoded, and converted to actual light intensities before interpolating. This keeps the
+    /// total brightness even during a crossfade. `t` is clamped to `0.0..=1.0`.
+    pub fn lerp_linear(a: Led, b: Led, t: f32) -> Led {
+        let t = t.clamp(0.0, 1.0);
+        a.zip_with(b, |a, b| {
+            let (a, b) = (srgb_to_linear(a), srgb_to_linear(b));
+            linear_to_srgb(a + (b - a) * t)
+        })
+    }
+
+    /// Interpolates between `a` and `b` in the perceptually uniform Oklab color space. This gives
+    /// the most natural looking transitions between different hues, without passing through
+    /// grey. The white channel is interpolated in linear light. `t` is clamped to `0.0..=1.0`.
+    pub fn lerp_oklab(a: Led, b: Led, t: f32) -> Led {
+        let t = t.clamp(0.0, 1.0);
+        let lab_a = a.to_oklab();
+        let lab_b = b.to_oklab();
+        let mix = |a: f32, b: f32| a + (b - a) * t;
+        let (r, g, bl) = oklab_to_linear_rgb([
+            mix(lab_a[0], lab_b[0]),
+            mix(lab_a[1], lab_b[1]),
+            mix(lab_a[2], lab_b[2]),
+        ]);
+        let white = mix(srgb_to_linear(a.white()), srgb_to_linear(b.white()));
+        Led::new(
+            linear_to_srgb(white),
+            linear_to_srgb(r),
+            linear_to_srgb(g),
+            linear_to_srgb(bl),
+        )
+    }
+
+    /// Blends `top` onto this color, which is treated as the bottom layer, using the given mode.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use rpi_ws281x::{BlendMode, Led};
+    /// let purple = Led::RED.blend(Led::BLUE, BlendMode::Max);
+    /// assert_eq!(purple, Led::new(0, 255, 0, 255));
+    /// ```
+    pub fn blend(self, top: Led, mode: BlendMode) -> Led {
+        match mode {
+            BlendMode::Alpha(alpha) => Led::lerp(self, top, alpha),
+            BlendMode::Multiply => self.zip_with(top, mul_div255),
+            BlendMode::Screen => self.zip_with(top, |a, b| 255 - mul_div255(255 - a, 255 - b)),
+            BlendMode::Max => self.zip_with(top, u8::max),
+            BlendMode::Subtract => self.zip_with(top, u8::saturating_sub),
+            BlendMode::Difference => self.zip_with(top, |a, b| a.max(b) - a.min(b)),
+        }
+    }
+
+    fn zip_with(self, other: Led, f: impl Fn(u8, u8) -> u8) -> Led {
+        Led::new(
+            f(self.white(), other.white()),
+            f(self.red(), other.red()),
+            f(self.green(), other.green()),
+            f(self.blue(), other.blue()),
+        )
+    }
+
+    fn to_oklab(self) -> [f32; 3] {
+        let r = srgb_to_linear(self.red());
+        let g = srgb_to_linear(self.green());
+        let b = srgb_to_linear(self.blue());
+
+        let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+        let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+        let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+
+        [
+            0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+            1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+            0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+        ]
+    }
+}
+
+/// Converts Oklab back into linear RGB intensities.
+fn oklab_to_linear_rgb([lightness, a, b]: [f32; 3]) -> (f32, f32, f32) {
+    let l = (lightness + 0.396_337_78 * a + 0.215_803_76 * b).powi(3);
+    let m = (lightness - 0.105_561_346 * a - 0.063_854_17 * b).powi(3);
+    let s = (lightness - 0.089_484_18 * a - 1.291_485_5 * b).powi(3);
+    (
+        4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s,
+        -1.268_438 * l + 2.609_757_4 * m - 0.341_319_38 * s,
+        -0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s,
+    )
+}
+
+/// Converts an sRGB encoded channel value into linear light intensity in `0.0..=1.0`.
+fn srgb_to_linear(value: u8) -> f32 {
+    let c = f32::from(value) / 255.0;
+    if c <= 0.040_45 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Converts a linear light intensity into an sRGB encoded channel value. Out of range
+/// intensities are clamped.
+fn linear_to_srgb(intensity: f32) -> u8 {
+    let c = intensity.clamp(0.0, 1.0);
+    let encoded = if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    };
+    (encoded * 255.0).round() as u8
+}
+
+fn mul_div255(a: u8, b: u8) -> u8 {
+    ((u16::from(a) * u16::from(b) + 127) / 255) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BlendMode;
+    use crate::Led;
+
+    #[test]
+    fn lerp() {
+        let a = Led::new(0, 10, 100, 200);
+        let b = Led::new(100, 20, 0, 255);
+        assert_eq!(Led::lerp(a, b, 0.0), a);
+        assert_eq!(Led::lerp(a, b, 1.0), b);
+        assert_eq!(Led::lerp(a, b, 0.5), Led::new(50, 15, 50, 228));
+        assert_eq!(Led::lerp(a, b, -3.0), a);
+        assert_eq!(Led::lerp(a, b, 7.0), b);
+    }
+
+    #[test]
+    fn lerp_linear() {
+        assert_eq!(Led::lerp_linear(Led::RED, Led::GREEN, 0.0), Led::RED);
+        assert_eq!(Led::lerp_linear(Led::RED, Led::GREEN, 1.0), Led::GREEN);
+        assert_eq!(
+            Led::lerp_linear(Led::RED, Led::GREEN, 0.5),
+            Led::new(0, 188, 188, 0)
+        );
+    }
+
+    #[test]
+    fn lerp_oklab() {
+        let close = |a: Led, b: Led| {
+            let diff = |x: u8, y: u8| (i16::from(x) - i16::from(y)).abs();
+            diff(a.white(), b.white()) <= 1
+                && diff(a.red(), b.red()) <= 1
+                && diff(a.green(), b.green()) <= 1
+                && diff(a.blue(), b.blue()) <= 1
+        };
+        let a = Led::new(0, 255, 40, 0);
+        let b = Led::new(255, 0, 90, 230);
+        assert!(close(Led::lerp_oklab(a, b, 0.0), a));
+        assert!(close(Led::lerp_oklab(a, b, 1.0), b));
+
+        // A crossfade between complementary colors does not go through grey.
+        let mid = Led::lerp_oklab(Led::BLUE, Led::new(0, 255, 255, 0), 0.5);
+        let (_, saturation, _) = mid.to_hsv();
+        assert!(saturation > 0.2, "{:?}", mid);
+        // And it is brighter than the naive midpoint.
+        let naive = Led::lerp(Led::RED, Led::GREEN, 0.5);
+        let oklab = Led::lerp_oklab(Led::RED, Led::GREEN, 0.5);
+        assert!(oklab.red() > naive.red() && oklab.green() > naive.green());
+    }
+
+    #[test]
+    fn blend_modes() {
+        let bottom = Led::new(0, 100, 200, 255);
+        let top = Led::new(255, 200, 100, 0);
+        assert_eq!(bottom.blend(top, BlendMode::Alpha(0.0)), bottom);
+        assert_eq!(bottom.blend(top, BlendMode::Alpha(1.0)), top);
+        assert_eq!(
+            bottom.blend(top, BlendMode::Multiply),
+            Led::new(0, 78, 78, 0)
+        );
+        assert_eq!(bottom.blend(Led::ON, BlendMode::Multiply), bottom);
+        assert_eq!(
+            bottom.blend(top, BlendMode::Screen),
+            Led::new(255, 222, 222, 255)
+        );
+        assert_eq!(bottom.blend(Led::OFF, BlendMode::Screen), bottom);
+        assert_eq!(
+            bottom.blend(top, BlendMode::Max),
+            Led::new(255, 200, 200, 255)
+        );
+        assert_eq!(
+            bottom.blend(top, BlendMode::Subtract),
+            Led::new(0, 0, 100, 255)
+        );
+        assert_eq!(
+            bottom.blend(top, BlendMode::Difference),
+            Led::new(255, 100, 100, 255)
+        );
+    }
+}
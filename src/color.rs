@@ -0,0 +1,273 @@
+use crate::{Led, WhiteExtraction};
+
+impl Led {
+    /// Creates an [`Led`] from a color in the HSV color space. `hue` is given in degrees and
+    /// wraps around, so any value is valid. `saturation` and `value` are clamped to `0.0..=1.0`.
+    /// The white channel is left off.
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> Self {
+        let saturation = saturation.clamp(0.0, 1.0);
+        let value = value.clamp(0.0, 1.0);
+        let chroma = value * saturation;
+        let (r, g, b) = hue_to_rgb(hue, chroma);
+        let m = value - chroma;
+        Self::new(0, to_u8(r + m), to_u8(g + m), to_u8(b + m))
+    }
+
+    /// Creates an [`Led`] from a color in the HSL color space. `hue` is given in degrees and
+    /// wraps around, so any value is valid. `saturation` and `lightness` are clamped to
+    /// `0.0..=1.0`. The white channel is left off.
+    pub fn from_hsl(hue: f32, saturation: f32, lightness: f32) -> Self {
+        let saturation = saturation.clamp(0.0, 1.0);
+        let lightness = lightness.clamp(0.0, 1.0);
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        let (r, g, b) = hue_to_rgb(hue, chroma);
+        let m = lightness - chroma / 2.0;
+        Self::new(0, to_u8(r + m), to_u8(g + m), to_u8(b + m))
+    }
+
+    /// Returns the RGB part of this [`Led`] as `(hue, saturation, value)`. The hue is in
+    /// degrees in the range `0.0..360.0`, the other two in `0.0..=1.0`. The white channel is
+    /// ignored.
+    pub fn to_hsv(&self) -> (f32, f32, f32) {
+        let (hue, max, min) = self.hue_max_min();
+        let saturation = if max > 0.0 { (max - min) / max } else { 0.0 };
+        (hue, saturation, max)
+    }
+
+    /// Returns the RGB part of this [`Led`] as `(hue, saturation, lightness)`. The hue is in
+    /// degrees in the range `0.0..360.0`, the other two in `0.0..=1.0`. The white channel is
+    /// ignored.
+    pub fn to_hsl(&self) -> (f32, f32, f32) {
+        let (hue, max, min) = self.hue_max_min();
+        let lightness = (max + min) / 2.0;
+        let saturation = if max == min {
+            0.0
+        } else {
+            (max - min) / (1.0 - (2.0 * lightness - 1.0).abs())
+        };
+        (hue, saturation, lightness)
+    }
+
+    /// Returns a copy of this [`Led`] with the hue of the RGB part rotated by the given
+    /// number of degrees. Saturation, value and the white channel are left unchanged.
+    pub fn rotate_hue(self, degrees: f32) -> Self {
+        let (hue, saturation, value) = self.to_hsv();
+        let rotated = Self::from_hsv(hue + degrees, saturation, value);
+        Self::new(self.white(), rotated.red(), rotated.green(), rotated.blue())
+    }
+
+    /// Creates an [`Led`] from a color in the HSV color space using only integer math.
+    /// Here the full hue circle is mapped to `0..=255`, so roughly 85 is green and 170 is blue.
+    /// Considerably faster than [`Led::from_hsv`], at the cost of some precision.
+    pub const fn from_hsv8(hue: u8, saturation: u8, value: u8) -> Self {
+        if saturation == 0 {
+            return Self::new(0, value, value, value);
+        }
+        let (h, s, v) = (hue as u16, saturation as u16, value as u16);
+        let region = h / 43;
+        let remainder = (h - region * 43) * 6;
+
+        let p = ((v * (255 - s)) >> 8) as u8;
+        let q = ((v * (255 - ((s * remainder) >> 8))) >> 8) as u8;
+        let t = ((v * (255 - ((s * (255 - remainder)) >> 8))) >> 8) as u8;
+        match region {
+            0 => Self::new(0, value, t, p),
+            1 => Self::new(0, q, value, p),
+            2 => Self::new(0, p, value, t),
+            3 => Self::new(0, p, q, value),
+            4 => Self::new(0, t, p, value),
+            _ => Self::new(0, value, p, q),
+        }
+    }
+
+    /// Returns the fully saturated, full brightness color at the given position on the color
+    /// wheel. Stepping `hue` by one every frame makes for a cheap rainbow effect.
+    /// See [`Led::from_hsv8`].
+    pub const fn rainbow(hue: u8) -> Self {
+        Self::from_hsv8(hue, 255, 255)
+    }
+
+    /// Creates an [`Led`] with the RGB channels mixed to the color of white light at the given
+    /// color temperature in Kelvin, scaled to the given brightness. The temperature is clamped
+    /// to 1000 K to 12000 K. Lower values are warmer (more orange), higher values are cooler
+    /// (more blue). Around 6600 K all RGB channels are at equal strength, giving the same color as
+    /// [`Led::RGB_WHITE`]. The white channel is left off.
+    ///
+    /// For strips with a white channel, see [`Led::from_kelvin_rgbw`].
+    pub fn from_kelvin(kelvin: u16, brightness: u8) -> Self {
+        let (r, g, b) = kelvin_to_rgb(f32::from(kelvin.clamp(1000, 12000)));
+        let scale = f32::from(brightness) / 255.0;
+        Self::new(0, to_u8(r * scale), to_u8(g * scale), to_u8(b * scale))
+    }
+
+    /// Like [`Led::from_kelvin`], but for RGBW strips. As much of the light as possible is
+    /// produced by the white channel, which is assumed to have a color temperature of
+    /// `white_kelvin`. The RGB channels make up for the difference in tint. Compared to
+    /// [`Led::WHITE`], this allows tuning the white to any temperature.
+    pub fn from_kelvin_rgbw(kelvin: u16, brightness: u8, white_kelvin: u16) -> Self {
+        Self::from_kelvin(kelvin, brightness)
+            .rgb_to_rgbw(WhiteExtraction::ColorTemperature(white_kelvin))
+    }
+
+    fn hue_max_min(&self) -> (f32, f32, f32) {
+        let r = f32::from(self.red()) / 255.0;
+        let g = f32::from(self.green()) / 255.0;
+        let b = f32::from(self.blue()) / 255.0;
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let delta = max - min;
+        let hue = if delta == 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        (hue, max, min)
+    }
+}
+
+/// Returns the RGB components, before lightness matching, for a color with the given hue
+/// in degrees and chroma.
+fn hue_to_rgb(hue: f32, chroma: f32) -> (f32, f32, f32) {
+    let h = hue.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (h.rem_euclid(2.0) - 1.0).abs());
+    match h as u8 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    }
+}
+
+/// Returns the color of a black body radiator at the given temperature in Kelvin, as RGB
+/// components in `0.0..=1.0`. Uses Tanner Helland's approximation, which is accurate enough
+/// for LEDs between 1000 K and 40000 K. The temperature is clamped to that range.
+pub(crate) fn kelvin_to_rgb(kelvin: f32) -> (f32, f32, f32) {
+    let t = kelvin.clamp(1000.0, 40000.0) / 100.0;
+    let r = if t <= 66.0 {
+        255.0
+    } else {
+        329.698_73 * (t - 60.0).powf(-0.133_204_76)
+    };
+    let g = if t <= 66.0 {
+        99.470_8 * t.ln() - 161.119_57
+    } else {
+        288.122_16 * (t - 60.0).powf(-0.075_514_85)
+    };
+    let b = if t >= 66.0 {
+        255.0
+    } else if t <= 19.0 {
+        0.0
+    } else {
+        138.517_73 * (t - 10.0).ln() - 305.044_8
+    };
+    (
+        (r / 255.0).clamp(0.0, 1.0),
+        (g / 255.0).clamp(0.0, 1.0),
+        (b / 255.0).clamp(0.0, 1.0),
+    )
+}
+
+fn to_u8(value: f32) -> u8 {
+    (value * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Led;
+
+    #[test]
+    fn from_hsv() {
+        assert_eq!(Led::from_hsv(0.0, 1.0, 1.0), Led::RED);
+        assert_eq!(Led::from_hsv(120.0, 1.0, 1.0), Led::GREEN);
+        assert_eq!(Led::from_hsv(240.0, 1.0, 1.0), Led::BLUE);
+        assert_eq!(Led::from_hsv(-120.0, 1.0, 1.0), Led::BLUE);
+        assert_eq!(Led::from_hsv(60.0, 1.0, 1.0), Led::new(0, 255, 255, 0));
+        assert_eq!(Led::from_hsv(0.0, 0.0, 1.0), Led::RGB_WHITE);
+        assert_eq!(Led::from_hsv(200.0, 1.0, 0.0), Led::OFF);
+        assert_eq!(Led::from_hsv(30.0, 0.5, 0.5), Led::new(0, 128, 96, 64));
+    }
+
+    #[test]
+    fn from_hsl() {
+        assert_eq!(Led::from_hsl(0.0, 1.0, 0.5), Led::RED);
+        assert_eq!(Led::from_hsl(240.0, 1.0, 0.5), Led::BLUE);
+        assert_eq!(Led::from_hsl(123.0, 1.0, 1.0), Led::RGB_WHITE);
+        assert_eq!(Led::from_hsl(123.0, 1.0, 0.0), Led::OFF);
+        assert_eq!(Led::from_hsl(180.0, 1.0, 0.25), Led::new(0, 0, 128, 128));
+    }
+
+    #[test]
+    fn hsv_round_trip() {
+        for &led in &[
+            Led::RED,
+            Led::new(0, 10, 200, 30),
+            Led::new(0, 250, 3, 128),
+            Led::new(0, 77, 77, 77),
+        ] {
+            let (h, s, v) = led.to_hsv();
+            assert_eq!(Led::from_hsv(h, s, v), led);
+            let (h, s, l) = led.to_hsl();
+            assert_eq!(Led::from_hsl(h, s, l), led);
+        }
+    }
+
+    #[test]
+    fn rotate_hue() {
+        assert_eq!(Led::RED.rotate_hue(120.0), Led::GREEN);
+        assert_eq!(Led::RED.rotate_hue(-120.0), Led::BLUE);
+        assert_eq!(
+            Led::new(40, 255, 0, 0).rotate_hue(240.0),
+            Led::new(40, 0, 0, 255)
+        );
+    }
+
+    #[test]
+    fn from_kelvin() {
+        assert_eq!(Led::from_kelvin(6600, 255), Led::RGB_WHITE);
+        assert_eq!(Led::from_kelvin(6600, 0), Led::OFF);
+        assert_eq!(Led::from_kelvin(1000, 255), Led::new(0, 255, 68, 0));
+        assert_eq!(Led::from_kelvin(0, 255), Led::from_kelvin(1000, 255));
+        assert_eq!(
+            Led::from_kelvin(u16::MAX, 255),
+            Led::from_kelvin(12000, 255)
+        );
+
+        let warm = Led::from_kelvin(2700, 255);
+        assert_eq!(warm.red(), 255);
+        assert!(warm.green() < 255 && warm.blue() < warm.green());
+        let cool = Led::from_kelvin(10000, 255);
+        assert_eq!(cool.blue(), 255);
+        assert!(cool.red() < 255);
+
+        let dim = Led::from_kelvin(2700, 128);
+        assert_eq!(dim.red(), 128);
+    }
+
+    #[test]
+    fn from_kelvin_rgbw() {
+        // Same temperature as the white LED only uses the white channel.
+        let led = Led::from_kelvin_rgbw(4000, 255, 4000);
+        assert!(led.white() >= 254);
+        assert!(led.red() <= 1 && led.green() <= 1 && led.blue() <= 1);
+        // Warmer than the white LED adds red.
+        let warmer = Led::from_kelvin_rgbw(2000, 255, 4000);
+        assert!(warmer.white() > 0);
+        assert!(warmer.red() > warmer.green());
+        assert_eq!(warmer.blue(), 0);
+    }
+
+    #[test]
+    fn from_hsv8() {
+        assert_eq!(Led::rainbow(0), Led::RED);
+        assert_eq!(Led::from_hsv8(85, 255, 255), Led::new(0, 3, 255, 0));
+        assert_eq!(Led::from_hsv8(172, 255, 255), Led::BLUE);
+        assert_eq!(Led::from_hsv8(50, 0, 99), Led::new(0, 99, 99, 99));
+        assert_eq!(Led::from_hsv8(50, 255, 0), Led::OFF);
+    }
+}
@@ -0,0 +1,232 @@
+use crate::Led;
+use std::fmt;
+
+/// A high precision version of [`Led`] with 16 bits per channel instead of 8.
+///
+/// Strips only take 8 bits per channel, so these values have to be reduced to [`Led`]s before
+/// rendering. Doing that with [`Dither`] spreads the extra precision over successive frames,
+/// which makes slow fades at low brightness look smooth instead of stepped.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub struct Led16 {
+    white: u16,
+    red: u16,
+    green: u16,
+    blue: u16,
+}
+
+impl Led16 {
+    /// All channels turned off.
+    pub const OFF: Self = Self::new(0, 0, 0, 0);
+
+    /// All channels on max brightness.
+    pub const ON: Self = Self::new(u16::MAX, u16::MAX, u16::MAX, u16::MAX);
+
+    /// Creates a new [`Led16`] instance with the given channel brightness values.
+    #[inline(always)]
+    pub const fn new(white: u16, red: u16, green: u16, blue: u16) -> Self {
+        Self {
+            white,
+            red,
+            green,
+            blue,
+        }
+    }
+
+    /// Returns the brightness value for the white channel.
+    pub const fn white(&self) -> u16 {
+        self.white
+    }
+
+    /// Returns the brightness value for the red channel.
+    pub const fn red(&self) -> u16 {
+        self.red
+    }
+
+    /// Returns the brightness value for the green channel.
+    pub const fn green(&self) -> u16 {
+        self.green
+    }
+
+    /// Returns the brightness value for the blue channel.
+    pub const fn blue(&self) -> u16 {
+        self.blue
+    }
+
+    fn channels(self) -> [u16; 4] {
+        [self.white, self.red, self.green, self.blue]
+    }
+}
+
+impl fmt::Debug for Led16 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Led16")
+            .field("w", &self.white)
+            .field("r", &self.red)
+            .field("g", &self.green)
+            .field("b", &self.blue)
+            .finish()
+    }
+}
+
+/// Converts an [`Led`] to the same color at 16 bit precision. 255 maps to 65535.
+impl From<Led> for Led16 {
+    fn from(led: Led) -> Self {
+        let expand = |v: u8| u16::from(v) * 257;
+        Self::new(
+            expand(led.white()),
+            expand(led.red()),
+            expand(led.green()),
+            expand(led.blue()),
+        )
+    }
+}
+
+impl core::ops::Mul<f32> for Led16 {
+    type Output = Self;
+    fn mul(self, rhs: f32) -> Self {
+        let scale = |v: u16| (f32::from(v) * rhs).round() as u16;
+        Self::new(
+            scale(self.white),
+            scale(self.red),
+            scale(self.green),
+            scale(self.blue),
+        )
+    }
+}
+
+impl core::ops::MulAssign<f32> for Led16 {
+    fn mul_assign(&mut self, rhs: f32) {
+        *self = *self * rhs;
+    }
+}
+
+/// Temporal dithering from [`Led16`] to [`Led`].
+///
+/// Keeps track of the rounding error of every channel of every LED between frames. Every
+/// frame, the accumulated error is added back before rounding down to 8 bits. So a value
+/// halfway between two 8 bit steps alternates between them, and averages out to the right
+/// brightness.
+///
+/// One `Dither` should be used per LED strip, and it should be fed every frame.
+#[derive(Debug, Clone, Default)]
+pub struct Dither {
+    /// The accumulated error per LED and channel, in 1/256ths of an 8 bit step.
+    error: Vec<[u8; 4]>,
+}
+
+impl Dither {
+    /// Creates a new `Dither` with no accumulated error.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reduces `input` to 8 bits per channel into `output`, scaling by `brightness` first.
+    /// Pass a brightness of 255 for no scaling. The scaling is the same as the C library does
+    /// on [`Led`] values, but done at 16 bit precision so low brightness does not destroy the
+    /// resolution of the input.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `input` and `output` have different lengths.
+    pub fn dither(&mut self, input: &[Led16], brightness: u8, output: &mut [Led]) {
+        assert_eq!(input.len(), output.len());
+        self.error.resize(input.len(), [0; 4]);
+
+        let scale = u32::from(brightness) + 1;
+        for ((led, error), out) in input.iter().zip(&mut self.error).zip(output) {
+            let mut channels = [0u8; 4];
+            for ((value, error), channel) in led.channels().iter().zip(error).zip(&mut channels) {
+                // Scale to 8.8 fixed point, where 65535 maps to 255.0. Brightness scaling is
+                // merged into the same operation.
+                let scaled = u32::from(*value) * scale / 257;
+                let accumulated = scaled + u32::from(*error);
+                let rounded = (accumulated >> 8).min(255);
+                *channel = rounded as u8;
+                *error = (accumulated - (rounded << 8)).min(255) as u8;
+            }
+            let [w, r, g, b] = channels;
+            *out = Led::new(w, r, g, b);
+        }
+    }
+
+    /// Forgets all accumulated error.
+    pub fn reset(&mut self) {
+        self.error.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Dither, Led16};
+    use crate::Led;
+
+    #[test]
+    fn from_led() {
+        assert_eq!(Led16::from(Led::ON), Led16::ON);
+        assert_eq!(Led16::from(Led::OFF), Led16::OFF);
+        assert_eq!(
+            Led16::from(Led::new(1, 2, 3, 128)),
+            Led16::new(257, 514, 771, 32896)
+        );
+    }
+
+    #[test]
+    fn exact_values_are_not_dithered() {
+        let mut dither = Dither::new();
+        let input = [Led16::from(Led::new(1, 2, 100, 255))];
+        let mut output = [Led::OFF];
+        for _ in 0..10 {
+            dither.dither(&input, 255, &mut output);
+            assert_eq!(output, [Led::new(1, 2, 100, 255)]);
+        }
+    }
+
+    #[test]
+    fn fractions_average_out() {
+        let mut dither = Dither::new();
+        let input = [Led16::new(385, 64, 2752, 65000)];
+        let mut output = [Led::OFF];
+        let mut sums = [0u32; 4];
+        let frames = 256;
+        for _ in 0..frames {
+            dither.dither(&input, 255, &mut output);
+            sums[0] += u32::from(output[0].white());
+            sums[1] += u32::from(output[0].red());
+            sums[2] += u32::from(output[0].green());
+            sums[3] += u32::from(output[0].blue());
+        }
+        for (sum, value) in sums.iter().zip(&[385, 64, 2752, 65000]) {
+            let expected = f64::from(*value) / 257.0 * f64::from(frames);
+            assert!(
+                (f64::from(*sum) - expected).abs() <= 1.0,
+                "{} {}",
+                sum,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn brightness_keeps_precision() {
+        let mut dither = Dither::new();
+        let input = [Led16::from(Led::new(0, 3, 0, 0))];
+        let mut output = [Led::OFF];
+        let mut sum = 0;
+        for _ in 0..4 {
+            dither.dither(&input, 127, &mut output);
+            sum += u32::from(output[0].red());
+        }
+        // Half of 3 is 1.5, which would be truncated to 1 without dithering.
+        assert_eq!(sum, 6);
+    }
+
+    #[test]
+    fn full_brightness_saturates() {
+        let mut dither = Dither::new();
+        let mut output = [Led::OFF];
+        for _ in 0..3 {
+            dither.dither(&[Led16::ON], 255, &mut output);
+            assert_eq!(output, [Led::ON]);
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use crate::{Led, Result, NUM_CHANNELS};
+use std::time::Duration;
+
+/// A hardware independent interface to something that can display LEDs.
+///
+/// [`Controller`](crate::Controller) implements this trait for real ws281x strips connected to a
+/// Raspberry Pi. Code written against `LedDriver` instead of a concrete controller type can be
+/// driven by real hardware, simulators or network outputs interchangeably.
+pub trait LedDriver {
+    /// Returns the number of channels this driver has. Channels that are disabled are still
+    /// counted, but have a length of zero.
+    fn channel_count(&self) -> usize {
+        NUM_CHANNELS
+    }
+
+    /// Returns the number of LEDs on the channel with the given index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel_index >= self.channel_count()`.
+    fn channel_len(&self, channel_index: usize) -> usize;
+
+    /// Returns a mutable slice where all the LED values for the given channel can be set directly.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel_index >= self.channel_count()`.
+    fn buffer(&mut self, channel_index: usize) -> &mut [Led];
+
+    /// Returns mutable slices to the LED values of all channels at once.
+    fn buffers(&mut self) -> [&mut [Led]; NUM_CHANNELS];
+
+    /// Returns the brightness of the channel with the given index, between 0 and 255.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel_index >= self.channel_count()`.
+    fn brightness(&self, channel_index: usize) -> u8;
+
+    /// Sets the brightness of the channel with the given index, between 0 and 255.
+    /// Takes effect on the next render.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel_index >= self.channel_count()`.
+    fn set_brightness(&mut self, channel_index: usize, brightness: u8);
+
+    /// Render what is currently in the buffers to the LEDs.
+    fn render(&mut self) -> Result<()>;
+
+    /// Renders the given buffers instead of the buffers held by the driver.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the `&[Led]` slices are not the same length as the corresponding
+    /// channel, as given by [`LedDriver::channel_len`].
+    fn render_buffer(&mut self, buffers: [&[Led]; NUM_CHANNELS]) -> Result<()>;
+
+    /// Returns the shortest possible time between two frames. Rendering more often than this
+    /// does not give a higher frame rate, since the previous frame must be fully sent to the LEDs
+    /// first. The default implementation returns zero.
+    fn min_frame_interval(&self) -> Duration {
+        Duration::from_secs(0)
+    }
+
+    /// Blocks until the previously rendered frame has been fully sent to the LEDs.
+    ///
+    /// The default implementation returns immediately, which is correct for drivers that
+    /// finish all output before `render` returns.
+    fn wait(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Renders what is currently in the buffers and waits for the frame to be fully sent to the
+    /// LEDs. See [`LedDriver::render`] and [`LedDriver::wait`].
+    fn render_and_wait(&mut self) -> Result<()> {
+        self.render()?;
+        self.wait()
+    }
+}
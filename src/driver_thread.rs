@@ -0,0 +1,117 @@
+use crate::{LedDriver, Result, NUM_CHANNELS};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// What the driver thread reports back after the driver has been created.
+pub(crate) struct DriverInfo {
+    pub(crate) channel_lens: [usize; NUM_CHANNELS],
+    pub(crate) brightness: [u8; NUM_CHANNELS],
+    pub(crate) min_frame_interval: Duration,
+}
+
+/// Spawns the thread behind [`ThreadedController`](crate::ThreadedController) and
+/// [`AsyncController`](crate::AsyncController), and creates the driver on it by calling `init`.
+/// The driver never leaves the thread, so it does not have to be [`Send`].
+///
+/// The outcome of `init` is passed to `report`, which hands it over to the spawning side.
+/// If `report` returns the state the thread needs, `run` is called with it and the driver.
+/// Otherwise the thread exits. If the spawning side never gets a report, `init` panicked, and
+/// [`resume_panic`] should be called.
+pub(crate) fn spawn<D, S, F, P, R>(init: F, report: P, run: R) -> JoinHandle<Result<()>>
+where
+    D: LedDriver,
+    F: FnOnce() -> Result<D> + Send + 'static,
+    P: FnOnce(Result<DriverInfo>) -> Option<S> + Send + 'static,
+    R: FnOnce(D, S) -> Result<()> + Send + 'static,
+{
+    thread::spawn(move || {
+        let driver = match init() {
+            Ok(driver) => driver,
+            Err(error) => {
+                report(Err(error));
+                return Ok(());
+            }
+        };
+        let info = DriverInfo {
+            channel_lens: [driver.channel_len(0), driver.channel_len(1)],
+            brightness: [driver.brightness(0), driver.brightness(1)],
+            min_frame_interval: driver.min_frame_interval(),
+        };
+        match report(Ok(info)) {
+            Some(state) => run(driver, state),
+            None => Ok(()),
+        }
+    })
+}
+
+/// Waits for a driver thread that exited without reporting back, and propagates its panic.
+pub(crate) fn resume_panic(thread: JoinHandle<Result<()>>) -> ! {
+    match thread.join() {
+        Ok(_) => unreachable!("driver thread exited without reporting"),
+        Err(panic) => std::panic::resume_unwind(panic),
+    }
+}
+
+/// A driver for testing the controllers running on a driver thread.
+#[cfg(test)]
+pub(crate) mod test_driver {
+    use crate::{Error, Led, LedDriver, Result, SimulatedController, NUM_CHANNELS};
+    use std::sync::mpsc;
+
+    /// Sends channel 0 of every rendered frame over a channel, along with its brightness.
+    /// Fails to render frames where the first LED is white.
+    pub(crate) struct Forward {
+        inner: SimulatedController,
+        frames: mpsc::Sender<(Vec<Led>, u8)>,
+    }
+
+    impl Forward {
+        pub(crate) fn new(
+            channel_lens: [usize; NUM_CHANNELS],
+            frames: mpsc::Sender<(Vec<Led>, u8)>,
+        ) -> Self {
+            Self {
+                inner: SimulatedController::new(channel_lens),
+                frames,
+            }
+        }
+    }
+
+    impl LedDriver for Forward {
+        fn channel_len(&self, channel_index: usize) -> usize {
+            self.inner.channel_len(channel_index)
+        }
+
+        fn buffer(&mut self, channel_index: usize) -> &mut [Led] {
+            self.inner.buffer(channel_index)
+        }
+
+        fn buffers(&mut self) -> [&mut [Led]; NUM_CHANNELS] {
+            self.inner.buffers()
+        }
+
+        fn brightness(&self, channel_index: usize) -> u8 {
+            self.inner.brightness(channel_index)
+        }
+
+        fn set_brightness(&mut self, channel_index: usize, brightness: u8) {
+            self.inner.set_brightness(channel_index, brightness)
+        }
+
+        fn render(&mut self) -> Result<()> {
+            let [channel0, channel1] = self.inner.buffers();
+            let buffers = [channel0.to_vec(), channel1.to_vec()];
+            self.render_buffer([&buffers[0], &buffers[1]])
+        }
+
+        fn render_buffer(&mut self, buffers: [&[Led]; NUM_CHANNELS]) -> Result<()> {
+            if buffers[0].first() == Some(&Led::WHITE) {
+                return Err(Error::SpiTransfer);
+            }
+            self.frames
+                .send((buffers[0].to_vec(), self.brightness(0)))
+                .unwrap();
+            Ok(())
+        }
+    }
+}
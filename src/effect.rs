@@ -0,0 +1,45 @@
+use crate::Led;
+use std::time::Duration;
+
+/// An animation that can be drawn on a strip of LEDs. See [`effects`](crate::effects) for the
+/// built-in ones.
+///
+/// Effects are driven by time rather than by frame, so they run at the same speed regardless
+/// of the frame rate. They work on a plain `&mut [Led]`, so they can be drawn on a channel
+/// buffer, part of one or a `Vec<Led>` in tests.
+///
+/// # Example
+///
+/// ```
+/// # use rpi_ws281x::{effects::Rainbow, Effect, Led, RenderLoop, SimulatedController};
+/// use std::ops::ControlFlow;
+///
+/// let mut controller = SimulatedController::new([10, 0]);
+/// let mut rainbow = Rainbow::new();
+/// RenderLoop::new(200.0)
+///     .run(&mut controller, |[strip, _], frame| {
+///         rainbow.render(frame.elapsed(), strip);
+///         ControlFlow::Break(())
+///     })
+///     .unwrap();
+/// assert_eq!(controller.last_frame().unwrap().buffer(0)[0], Led::RED);
+/// ```
+pub trait Effect {
+    /// Draws the effect as it looks at time `t` into `leds`, overwriting what was there. `t` is
+    /// the time since the effect started, and is expected to increase between calls. Effects
+    /// with state, such as [`Fire`](crate::effects::Fire), advance it by the time passed since
+    /// the previous call.
+    fn render(&mut self, t: Duration, leds: &mut [Led]);
+}
+
+impl<E: Effect + ?Sized> Effect for Box<E> {
+    fn render(&mut self, t: Duration, leds: &mut [Led]) {
+        (**self).render(t, leds)
+    }
+}
+
+impl<E: Effect + ?Sized> Effect for &mut E {
+    fn render(&mut self, t: Duration, leds: &mut [Led]) {
+        (**self).render(t, leds)
+    }
+}
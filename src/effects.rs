@@ -0,0 +1,706 @@
+//! Built-in [`Effect`]s.
+//!
+//! Every effect draws on the whole slice it is given. To show an effect on part of a strip, pass
+//! it a sub slice. Effects that use randomness are seeded, so they run the same every time
+//! unless another seed is given.
+
+use crate::{Effect, Led};
+use std::f32::consts::TAU;
+use std::time::Duration;
+
+/// The seed used by effects with randomness, unless another one is given.
+const DEFAULT_SEED: u32 = 0x2545_f491;
+
+/// Colors across the whole hue circle, scrolling along the strip.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rainbow {
+    speed: f32,
+    repeat: f32,
+}
+
+impl Rainbow {
+    /// Creates a rainbow showing the hue circle once along the strip, scrolling once every five
+    /// seconds.
+    pub fn new() -> Self {
+        Self {
+            speed: 0.2,
+            repeat: 1.0,
+        }
+    }
+
+    /// Sets how many times per second the colors scroll past. Negative values scroll the other
+    /// way.
+    pub fn speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Sets how many times the hue circle is repeated along the strip.
+    pub fn repeat(mut self, repeat: f32) -> Self {
+        self.repeat = repeat;
+        self
+    }
+}
+
+impl Default for Rainbow {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Effect for Rainbow {
+    fn render(&mut self, t: Duration, leds: &mut [Led]) {
+        let offset = t.as_secs_f32() * self.speed;
+        let len = leds.len() as f32;
+        for (i, led) in leds.iter_mut().enumerate() {
+            let position = offset + i as f32 / len * self.repeat;
+            *led = Led::from_hsv(position.rem_euclid(1.0) * 360.0, 1.0, 1.0);
+        }
+    }
+}
+
+/// Fills the strip with a color one LED at a time, then wipes it back to the background color
+/// the same way, over and over.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorWipe {
+    color: Led,
+    background: Led,
+    duration: Duration,
+}
+
+impl ColorWipe {
+    /// Creates a wipe that fills the strip with `color` over `duration`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `duration` is zero.
+    pub fn new(color: Led, duration: Duration) -> Self {
+        assert!(
+            duration > Duration::from_secs(0),
+            "duration must be positive"
+        );
+        Self {
+            color,
+            background: Led::OFF,
+            duration,
+        }
+    }
+
+    /// Sets the color the strip is wiped back to. Defaults to [`Led::OFF`].
+    pub fn background(mut self, background: Led) -> Self {
+        self.background = background;
+        self
+    }
+}
+
+impl Effect for ColorWipe {
+    fn render(&mut self, t: Duration, leds: &mut [Led]) {
+        let cycles = t.as_secs_f64() / self.duration.as_secs_f64();
+        let (fill, under) = if (cycles as u64) % 2 == 1 {
+            (self.background, self.color)
+        } else {
+            (self.color, self.background)
+        };
+        let filled = ((cycles.fract() * (leds.len() + 1) as f64) as usize).min(leds.len());
+        let (head, tail) = leds.split_at_mut(filled);
+        head.fill(fill);
+        tail.fill(under);
+    }
+}
+
+/// Every few LEDs lit, stepping along the strip like the chaser lights around theater signs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TheaterChase {
+    color: Led,
+    background: Led,
+    speed: f32,
+    spacing: usize,
+}
+
+impl TheaterChase {
+    /// Creates a chase where every third LED is lit, moving `speed` steps per second.
+    pub fn new(color: Led, speed: f32) -> Self {
+        Self {
+            color,
+            background: Led::OFF,
+            speed,
+            spacing: 3,
+        }
+    }
+
+    /// Sets the color of the LEDs that are not lit. Defaults to [`Led::OFF`].
+    pub fn background(mut self, background: Led) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// Sets the distance between lit LEDs. Defaults to 3.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `spacing` is zero.
+    pub fn spacing(mut self, spacing: usize) -> Self {
+        assert!(spacing > 0, "spacing must be positive");
+        self.spacing = spacing;
+        self
+    }
+}
+
+impl Effect for TheaterChase {
+    fn render(&mut self, t: Duration, leds: &mut [Led]) {
+        let step = (t.as_secs_f32() * self.speed).floor() as i64;
+        let offset = step.rem_euclid(self.spacing as i64) as usize;
+        for (i, led) in leds.iter_mut().enumerate() {
+            *led = if i % self.spacing == offset {
+                self.color
+            } else {
+                self.background
+            };
+        }
+    }
+}
+
+/// A bright head with a fading tail, shooting along the strip. Also known as a meteor.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comet {
+    color: Led,
+    speed: f32,
+    tail: f32,
+}
+
+impl Comet {
+    /// Creates a comet moving `speed` LEDs per second. Once the tail has left the end of the
+    /// strip, it starts over from the beginning.
+    pub fn new(color: Led, speed: f32) -> Self {
+        Self {
+            color,
+            speed,
+            tail: 8.0,
+        }
+    }
+
+    /// Sets the length of the tail in LEDs. Defaults to 8.
+    pub fn tail(mut self, tail: f32) -> Self {
+        self.tail = tail.max(1.0);
+        self
+    }
+}
+
+impl Effect for Comet {
+    fn render(&mut self, t: Duration, leds: &mut [Led]) {
+        let head = (t.as_secs_f32() * self.speed).rem_euclid(leds.len() as f32 + self.tail);
+        for (i, led) in leds.iter_mut().enumerate() {
+            let distance = head - i as f32;
+            *led = if (0.0..self.tail).contains(&distance) {
+                self.color * (1.0 - distance / self.tail).powi(2)
+            } else {
+                Led::OFF
+            };
+        }
+    }
+}
+
+/// LEDs that light up at random and slowly fade out again, like stars.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Twinkle {
+    color: Led,
+    density: f32,
+    fade_time: Duration,
+    rng: XorShift32,
+    levels: Vec<f32>,
+    last_t: Option<Duration>,
+}
+
+impl Twinkle {
+    /// Creates a twinkle effect in the given color.
+    pub fn new(color: Led) -> Self {
+        Self {
+            color,
+            density: 0.1,
+            fade_time: Duration::from_secs(1),
+            rng: XorShift32::new(DEFAULT_SEED),
+            levels: Vec::new(),
+            last_t: None,
+        }
+    }
+
+    /// Sets how many of the LEDs are lit on average, between 0.0 and 1.0. Defaults to 0.1.
+    pub fn density(mut self, density: f32) -> Self {
+        self.density = density.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Sets how long it takes for an LED to fade out after lighting up. Defaults to one second.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `fade_time` is zero.
+    pub fn fade_time(mut self, fade_time: Duration) -> Self {
+        assert!(
+            fade_time > Duration::from_secs(0),
+            "fade time must be positive"
+        );
+        self.fade_time = fade_time;
+        self
+    }
+
+    /// Sets the seed of the random number generator.
+    pub fn seed(mut self, seed: u32) -> Self {
+        self.rng = XorShift32::new(seed);
+        self
+    }
+}
+
+impl Effect for Twinkle {
+    fn render(&mut self, t: Duration, leds: &mut [Led]) {
+        let dt = elapsed_since(&mut self.last_t, t).as_secs_f32();
+        let fade = dt / self.fade_time.as_secs_f32();
+        // Every LED that lights up stays lit for the fade time. This chance of lighting up
+        // gives the requested fraction of lit LEDs on average.
+        let spawn_chance = self.density * fade / (1.0 - self.density);
+        self.levels.resize(leds.len(), 0.0);
+        for (level, led) in self.levels.iter_mut().zip(leds) {
+            *level = (*level - fade).max(0.0);
+            if *level == 0.0 && self.rng.next_f32() < spawn_chance {
+                *level = 1.0;
+            }
+            *led = self.color * *level;
+        }
+    }
+}
+
+/// A color slowly pulsing between dark and full brightness.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Breathing {
+    color: Led,
+    period: Duration,
+    min_brightness: f32,
+}
+
+impl Breathing {
+    /// Creates a pulsing effect in the given color, doing one full breath per `period`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `period` is zero.
+    pub fn new(color: Led, period: Duration) -> Self {
+        assert!(period > Duration::from_secs(0), "period must be positive");
+        Self {
+            color,
+            period,
+            min_brightness: 0.0,
+        }
+    }
+
+    /// Sets the brightness at the bottom of each breath, between 0.0 and 1.0. Defaults to 0.0.
+    pub fn min_brightness(mut self, min_brightness: f32) -> Self {
+        self.min_brightness = min_brightness.clamp(0.0, 1.0);
+        self
+    }
+}
+
+impl Effect for Breathing {
+    fn render(&mut self, t: Duration, leds: &mut [Led]) {
+        let phase = (t.as_secs_f64() / self.period.as_secs_f64()).fract() as f32;
+        let level = (1.0 - (phase * TAU).cos()) / 2.0;
+        let brightness = self.min_brightness + (1.0 - self.min_brightness) * level;
+        leds.fill(self.color * brightness);
+    }
+}
+
+/// Flames rising from the start of the strip. A port of the classic Fire2012 effect.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fire {
+    cooling: u8,
+    sparking: u8,
+    rng: XorShift32,
+    heat: Vec<u8>,
+    last_t: Option<Duration>,
+    pending: Duration,
+}
+
+impl Fire {
+    /// The simulation runs in fixed steps of this length, independently of the frame rate.
+    const STEP: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+    /// The maximum number of steps run in one render, to catch up after a pause.
+    const MAX_STEPS: u32 = 10;
+
+    /// Creates a fire with the default settings of Fire2012.
+    pub fn new() -> Self {
+        Self {
+            cooling: 55,
+            sparking: 120,
+            rng: XorShift32::new(DEFAULT_SEED),
+            heat: Vec::new(),
+            last_t: None,
+            pending: Duration::from_secs(0),
+        }
+    }
+
+    /// Sets how fast the flames cool down. Higher values give shorter flames. Defaults to 55.
+    pub fn cooling(mut self, cooling: u8) -> Self {
+        self.cooling = cooling;
+        self
+    }
+
+    /// Sets the chance, out of 255, of a new spark every step. Higher values give a more
+    /// roaring fire. Defaults to 120.
+    pub fn sparking(mut self, sparking: u8) -> Self {
+        self.sparking = sparking;
+        self
+    }
+
+    /// Sets the seed of the random number generator.
+    pub fn seed(mut self, seed: u32) -> Self {
+        self.rng = XorShift32::new(seed);
+        self
+    }
+
+    fn step(&mut self) {
+        let len = self.heat.len();
+        let max_cooling = u32::from(self.cooling) * 10 / len as u32 + 2;
+        for heat in &mut self.heat {
+            let cooling = self.rng.below(max_cooling + 1).min(255) as u8;
+            *heat = heat.saturating_sub(cooling);
+        }
+        // Heat drifts up and diffuses.
+        for i in (2..len).rev() {
+            let sum = u16::from(self.heat[i - 1]) + 2 * u16::from(self.heat[i - 2]);
+            self.heat[i] = (sum / 3) as u8;
+        }
+        if self.rng.below(255) < u32::from(self.sparking) {
+            let i = self.rng.below(len.min(7) as u32) as usize;
+            let spark = 160 + self.rng.below(96) as u8;
+            self.heat[i] = self.heat[i].saturating_add(spark);
+        }
+    }
+}
+
+impl Default for Fire {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Effect for Fire {
+    fn render(&mut self, t: Duration, leds: &mut [Led]) {
+        if leds.is_empty() {
+            return;
+        }
+        let first = self.last_t.is_none() || self.heat.len() != leds.len();
+        self.heat.resize(leds.len(), 0);
+        self.pending += elapsed_since(&mut self.last_t, t);
+        let mut steps = 0;
+        while (first && steps == 0) || (self.pending >= Self::STEP && steps < Self::MAX_STEPS) {
+            self.pending = self.pending.saturating_sub(Self::STEP);
+            self.step();
+            steps += 1;
+        }
+        if steps == Self::MAX_STEPS {
+            self.pending = Duration::from_secs(0);
+        }
+        for (&heat, led) in self.heat.iter().zip(leds) {
+            *led = heat_color(heat);
+        }
+    }
+}
+
+/// Maps a heat value to a color going from black through red and yellow to white.
+fn heat_color(heat: u8) -> Led {
+    let heat = (u16::from(heat) * 191 / 255) as u8;
+    let ramp = (heat & 0x3f) << 2;
+    if heat & 0x80 != 0 {
+        Led::new(0, 255, 255, ramp)
+    } else if heat & 0x40 != 0 {
+        Led::new(0, 255, ramp, 0)
+    } else {
+        Led::new(0, ramp, 0, 0)
+    }
+}
+
+/// Smoothly flowing colors, made from a few overlapping sine waves.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plasma {
+    speed: f32,
+    scale: f32,
+}
+
+impl Plasma {
+    /// Creates a plasma with a normal speed and scale.
+    pub fn new() -> Self {
+        Self {
+            speed: 1.0,
+            scale: 1.0,
+        }
+    }
+
+    /// Sets how fast the plasma moves. Defaults to 1.0.
+    pub fn speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Sets how many waves fit on the strip. Larger values give smaller details. Defaults
+    /// to 1.0.
+    pub fn scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+}
+
+impl Default for Plasma {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Effect for Plasma {
+    fn render(&mut self, t: Duration, leds: &mut [Led]) {
+        let t = t.as_secs_f32() * self.speed;
+        let len = leds.len() as f32;
+        for (i, led) in leds.iter_mut().enumerate() {
+            let x = i as f32 / len * TAU * self.scale;
+            let value = (x + t).sin() + (0.5 * x - 1.3 * t).sin() + (1.7 * x + 0.6 * t).sin();
+            // `value` is within -3.0..=3.0.
+            *led = Led::from_hsv(value * 60.0 + t * 20.0, 1.0, 1.0);
+        }
+    }
+}
+
+/// Soft blobs of color circling around the strip, adding up where they overlap. Looks best on
+/// strips that form a closed loop.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GaussianBlobs {
+    blobs: Vec<Blob>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Blob {
+    color: Led,
+    speed: f32,
+    width: f32,
+}
+
+impl GaussianBlobs {
+    /// Creates the effect without any blobs.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a blob of the given color, circling `speed` times around the strip per second.
+    /// Negative speeds circle the other way. `width` is the standard deviation of the blob, as a
+    /// fraction of the length of the strip.
+    pub fn blob(mut self, color: Led, speed: f32, width: f32) -> Self {
+        self.blobs.push(Blob {
+            color,
+            speed,
+            width,
+        });
+        self
+    }
+}
+
+impl Effect for GaussianBlobs {
+    fn render(&mut self, t: Duration, leds: &mut [Led]) {
+        leds.fill(Led::OFF);
+        let len = leds.len() as f32;
+        for blob in &self.blobs {
+            let center = (t.as_secs_f32() * blob.speed).rem_euclid(1.0);
+            let variance = blob.width.powi(2);
+            for (i, led) in leds.iter_mut().enumerate() {
+                // The shortest distance around the loop, within -0.5..0.5.
+                let distance = (center - i as f32 / len + 0.5).rem_euclid(1.0) - 0.5;
+                *led += blob.color * (distance.powi(2) / (-2.0 * variance)).exp();
+            }
+        }
+    }
+}
+
+/// Returns the time since the previous call, and stores `t` for the next one. Returns zero on
+/// the first call, and if time went backwards.
+fn elapsed_since(last_t: &mut Option<Duration>, t: Duration) -> Duration {
+    let previous = last_t.replace(t).unwrap_or(t);
+    t.checked_sub(previous).unwrap_or_default()
+}
+
+/// A small and fast pseudo random number generator. Not suitable for anything but effects.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct XorShift32(u32);
+
+impl XorShift32 {
+    pub(crate) fn new(seed: u32) -> Self {
+        // The state must never be zero.
+        Self(if seed == 0 { DEFAULT_SEED } else { seed })
+    }
+
+    pub(crate) fn next_u32(&mut self) -> u32 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.0 = x;
+        x
+    }
+
+    /// Returns a number in `0.0..1.0`.
+    pub(crate) fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1 << 24) as f32
+    }
+
+    /// Returns a number in `0..n`.
+    pub(crate) fn below(&mut self, n: u32) -> u32 {
+        ((u64::from(self.next_u32()) * u64::from(n)) >> 32) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(effect: &mut impl Effect, millis: u64, len: usize) -> Vec<Led> {
+        let mut leds = vec![Led::ON; len];
+        effect.render(Duration::from_millis(millis), &mut leds);
+        leds
+    }
+
+    #[test]
+    fn rainbow() {
+        let mut rainbow = Rainbow::new().speed(1.0).repeat(2.0);
+        let leds = render(&mut rainbow, 0, 6);
+        assert_eq!(leds[0], Led::RED);
+        assert_eq!(leds[1], Led::GREEN);
+        assert_eq!(leds[2], Led::BLUE);
+        assert_eq!(leds[3], Led::RED);
+        // Half a cycle later, red has turned into cyan.
+        assert_eq!(render(&mut rainbow, 500, 6)[0], Led::new(0, 0, 255, 255));
+    }
+
+    #[test]
+    fn color_wipe() {
+        let mut wipe = ColorWipe::new(Led::RED, Duration::from_secs(1)).background(Led::BLUE);
+        assert_eq!(render(&mut wipe, 0, 3), [Led::BLUE; 3]);
+        assert_eq!(render(&mut wipe, 500, 3), [Led::RED, Led::RED, Led::BLUE]);
+        assert_eq!(render(&mut wipe, 999, 3), [Led::RED; 3]);
+        assert_eq!(render(&mut wipe, 1250, 3), [Led::BLUE, Led::RED, Led::RED]);
+    }
+
+    #[test]
+    fn theater_chase() {
+        let mut chase = TheaterChase::new(Led::RED, 2.0).spacing(2);
+        assert_eq!(
+            render(&mut chase, 0, 4),
+            [Led::RED, Led::OFF, Led::RED, Led::OFF]
+        );
+        assert_eq!(
+            render(&mut chase, 500, 4),
+            [Led::OFF, Led::RED, Led::OFF, Led::RED]
+        );
+    }
+
+    #[test]
+    fn comet() {
+        let mut comet = Comet::new(Led::RED, 10.0).tail(2.0);
+        let leds = render(&mut comet, 300, 6);
+        assert_eq!(
+            leds,
+            [
+                Led::OFF,
+                Led::OFF,
+                Led::new(0, 64, 0, 0),
+                Led::RED,
+                Led::OFF,
+                Led::OFF
+            ]
+        );
+        // The tail leaves the strip before the comet starts over.
+        assert_eq!(render(&mut comet, 650, 6)[5], Led::new(0, 16, 0, 0));
+        assert_eq!(
+            render(&mut comet, 800, 6),
+            [Led::RED, Led::OFF, Led::OFF, Led::OFF, Led::OFF, Led::OFF]
+        );
+    }
+
+    #[test]
+    fn twinkle() {
+        let mut twinkle = Twinkle::new(Led::BLUE).density(0.2).seed(7);
+        let mut leds = vec![Led::OFF; 500];
+        twinkle.render(Duration::from_secs(0), &mut leds);
+        assert!(leds.iter().all(|&led| led == Led::OFF));
+        let mut lit = 0;
+        for frame in 1..=1000 {
+            twinkle.render(Duration::from_millis(frame * 10), &mut leds);
+            assert!(leds.iter().all(|led| led.red() == 0 && led.white() == 0));
+            if frame > 500 {
+                lit += leds.iter().filter(|led| led.blue() > 0).count();
+            }
+        }
+        let average = lit as f32 / 500.0 / 500.0;
+        assert!((0.15..0.25).contains(&average), "{}", average);
+    }
+
+    #[test]
+    fn breathing() {
+        let mut breathing = Breathing::new(Led::GREEN, Duration::from_secs(2)).min_brightness(0.2);
+        assert_eq!(render(&mut breathing, 0, 2), [Led::new(0, 0, 51, 0); 2]);
+        assert_eq!(render(&mut breathing, 1000, 2), [Led::GREEN; 2]);
+        assert_eq!(render(&mut breathing, 2000, 2), [Led::new(0, 0, 51, 0); 2]);
+    }
+
+    #[test]
+    fn fire() {
+        let mut fire = Fire::new().seed(1);
+        let mut leds = vec![Led::OFF; 30];
+        for frame in 0..120 {
+            fire.render(Duration::from_millis(frame * 16), &mut leds);
+        }
+        assert!(leds[..7].iter().any(|&led| led != Led::OFF));
+        // Flames are never blue, and red is always the strongest.
+        assert!(leds
+            .iter()
+            .all(|led| led.red() >= led.green() && led.green() >= led.blue()));
+        assert_eq!(heat_color(0), Led::OFF);
+        assert_eq!(heat_color(255), Led::new(0, 255, 255, 252));
+
+        // Same seed, same flames.
+        let mut other = Fire::new().seed(1);
+        let mut other_leds = vec![Led::OFF; 30];
+        for frame in 0..120 {
+            other.render(Duration::from_millis(frame * 16), &mut other_leds);
+        }
+        assert_eq!(leds, other_leds);
+    }
+
+    #[test]
+    fn plasma() {
+        let mut plasma = Plasma::new();
+        let a = render(&mut plasma, 100, 20);
+        let b = render(&mut plasma, 100, 20);
+        let c = render(&mut plasma, 600, 20);
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert!(a.iter().all(|&led| led != Led::OFF));
+    }
+
+    #[test]
+    fn gaussian_blobs() {
+        let mut blobs =
+            GaussianBlobs::new()
+                .blob(Led::RED, 0.25, 0.05)
+                .blob(Led::BLUE, -0.25, 0.05);
+        let leds = render(&mut blobs, 1000, 8);
+        // Red is a quarter around the loop, blue three quarters.
+        assert_eq!(leds[2], Led::RED);
+        assert_eq!(leds[6], Led::BLUE);
+        assert_eq!(leds[0], Led::OFF);
+        assert_eq!(leds[4], Led::OFF);
+    }
+
+    #[test]
+    fn xorshift() {
+        let mut rng = XorShift32::new(0);
+        assert_ne!(rng.next_u32(), 0);
+        assert!((0..1000).all(|_| rng.below(7) < 7));
+        assert!((0..1000).all(|_| (0.0..1.0).contains(&rng.next_f32())));
+    }
+}
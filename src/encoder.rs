@@ -0,0 +1,329 @@
+use crate::{gamma, sys, GammaTable, Led, StripType, LED_RESET_TIME};
+use std::iter;
+
+/// Every data bit is sent as three output bits, clocked at three times the LED frequency. A one
+/// is a long high pulse and a zero is a short one.
+const SYMBOL_HIGH: u8 = 0b110;
+const SYMBOL_LOW: u8 = 0b100;
+/// The symbols to use when the output is inverted by a level shifter.
+const SYMBOL_HIGH_INV: u8 = 0b001;
+const SYMBOL_LOW_INV: u8 = 0b011;
+/// Number of output bits per data bit.
+pub(crate) const SYMBOL_BITS: u32 = 3;
+
+/// Turns LED values into exactly what is sent on the wire, in pure Rust.
+///
+/// Follows the same steps as the C library: brightness scaling, gamma correction, reordering the
+/// color channels for the strip type, and encoding every bit as a three bit symbol followed by
+/// the reset latch. This makes it possible to verify the output for a configuration on any
+/// machine.
+///
+/// # Example
+///
+/// ```
+/// # use rpi_ws281x::{Encoder, Led, StripType};
+/// let encoder = Encoder::new(StripType::Grb);
+/// let bytes: Vec<u8> = encoder.color_bytes(&[Led::new(0, 1, 2, 3)]).collect();
+/// assert_eq!(bytes, [2, 1, 3]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Encoder {
+    strip_type: u32,
+    brightness: u8,
+    invert: bool,
+    gamma: GammaTable,
+    freq: u32,
+}
+
+impl Encoder {
+    /// Creates an encoder for the given strip type, at full brightness, without gamma correction
+    /// or inversion, at 800 kHz.
+    pub fn new(strip_type: StripType) -> Self {
+        Self {
+            strip_type: strip_type as u32,
+            brightness: 255,
+            invert: false,
+            gamma: gamma::table_from_factor(0.0),
+            freq: sys::WS2811_TARGET_FREQ,
+        }
+    }
+
+    /// Creates an encoder with the same settings as the given C channel struct.
+    pub(crate) fn from_raw(
+        channel: &sys::ws2811_channel_t,
+        freq: u32,
+        gamma: Option<&GammaTable>,
+    ) -> Self {
+        Self {
+            strip_type: channel.strip_type as u32,
+            brightness: channel.brightness,
+            invert: channel.invert != 0,
+            gamma: gamma
+                .copied()
+                .unwrap_or_else(|| gamma::table_from_factor(0.0)),
+            freq,
+        }
+    }
+
+    /// Returns the brightness, between 0 and 255.
+    pub fn brightness(&self) -> u8 {
+        self.brightness
+    }
+
+    /// Sets the brightness, between 0 and 255. Scales all values the same way as
+    /// [`ChannelBuilder::brightness`](crate::ChannelBuilder::brightness).
+    pub fn set_brightness(&mut self, brightness: u8) {
+        self.brightness = brightness;
+    }
+
+    /// Returns `true` if the output is inverted.
+    pub fn invert(&self) -> bool {
+        self.invert
+    }
+
+    /// Sets if the output should be inverted. Only affects [`Encoder::encode_spi`], since the
+    /// PWM hardware inverts the signal itself.
+    pub fn set_invert(&mut self, invert: bool) {
+        self.invert = invert;
+    }
+
+    /// Sets the gamma correction table, which is applied after brightness.
+    pub fn set_gamma_table(&mut self, table: GammaTable) {
+        self.gamma = table;
+    }
+
+    /// Returns the LED frequency in Hz.
+    pub fn freq(&self) -> u32 {
+        self.freq
+    }
+
+    /// Sets the LED frequency in Hz. This affects the length of the reset latch.
+    pub fn set_freq(&mut self, freq: u32) {
+        self.freq = freq;
+    }
+
+    /// Returns the number of data bits sent per LED. 32 for strips with a white channel,
+    /// 24 otherwise.
+    pub fn bits_per_led(&self) -> usize {
+        self.channel_shifts().count() * 8
+    }
+
+    /// Returns the number of low output bits sent after the data to latch the LEDs.
+    pub fn reset_bits(&self) -> usize {
+        let symbol_rate = u64::from(self.freq) * u64::from(SYMBOL_BITS);
+        (LED_RESET_TIME.as_micros() as u64 * symbol_rate / 1_000_000) as usize
+    }
+
+    /// Returns the color values sent to the LEDs, after brightness and gamma correction, in the
+    /// order they are sent for the strip type.
+    pub fn color_bytes<'a>(&'a self, leds: &'a [Led]) -> impl Iterator<Item = u8> + 'a {
+        let scale = u32::from(self.brightness) + 1;
+        leds.iter().flat_map(move |&led| {
+            let raw = sys::ws2811_led_t::from(led);
+            self.channel_shifts().map(move |shift| {
+                let value = (raw >> shift) & 0xff;
+                self.gamma[((value * scale) >> 8) as usize]
+            })
+        })
+    }
+
+    /// Returns the data bits sent to the LEDs, most significant bit first. These are the
+    /// logical bits, before they are encoded as symbols.
+    pub fn bits<'a>(&'a self, leds: &'a [Led]) -> impl Iterator<Item = bool> + 'a {
+        self.color_bytes(leds)
+            .flat_map(|byte| (0..8).rev().map(move |bit| byte & (1 << bit) != 0))
+    }
+
+    /// Encodes the LEDs into the bytes written to the SPI device, clocked at three times the LED
+    /// frequency. Includes the reset latch, and applies inversion. Replaces the contents
+    /// of `out`.
+    pub fn encode_spi(&self, leds: &[Led], out: &mut Vec<u8>) {
+        out.clear();
+        pack(self.symbols(leds, self.invert), 8, |byte| {
+            out.push(byte as u8)
+        });
+    }
+
+    /// Encodes the LEDs into the symbol stream of one PWM channel, clocked at three times the LED
+    /// frequency and packed into 32 bit words. Includes the reset latch. Inversion is done by the
+    /// PWM hardware and is not applied. Replaces the contents of `out`.
+    ///
+    /// This is not the DMA buffer of the C library, which interleaves the words of both PWM
+    /// channels.
+    pub fn encode_pwm(&self, leds: &[Led], out: &mut Vec<u32>) {
+        out.clear();
+        pack(self.symbols(leds, false), 32, |word| out.push(word));
+    }
+
+    fn channel_shifts(&self) -> impl Iterator<Item = u32> {
+        channel_shifts(self.strip_type)
+    }
+
+    /// All output bits, including the reset latch.
+    fn symbols<'a>(&'a self, leds: &'a [Led], invert: bool) -> impl Iterator<Item = bool> + 'a {
+        let (high, low) = if invert {
+            (SYMBOL_HIGH_INV, SYMBOL_LOW_INV)
+        } else {
+            (SYMBOL_HIGH, SYMBOL_LOW)
+        };
+        self.bits(leds)
+            .flat_map(move |bit| {
+                let symbol = if bit { high } else { low };
+                (0..SYMBOL_BITS).rev().map(move |i| symbol & (1 << i) != 0)
+            })
+            .chain(iter::repeat(false).take(self.reset_bits()))
+    }
+}
+
+/// The bit offsets of the channels in a raw [`Led`] value, in the order they are sent for the
+/// given raw strip type. Same as the `rshift`, `gshift`, `bshift` and `wshift` computed by the
+/// C library.
+pub(crate) fn channel_shifts(strip_type: u32) -> impl Iterator<Item = u32> {
+    let has_white = matches!(
+        StripType::from_raw(strip_type),
+        Some(strip_type) if strip_type.has_white()
+    );
+    let colors = if has_white { 4 } else { 3 };
+    [16, 8, 0, 24]
+        .iter()
+        .map(move |offset| (strip_type >> offset) & 0xff)
+        .take(colors)
+}
+
+/// Packs bits into words of `width` bits, most significant bit first. The last word is padded
+/// with zeros.
+fn pack(bits: impl Iterator<Item = bool>, width: u32, mut push: impl FnMut(u32)) {
+    let mut word = 0;
+    let mut used = 0;
+    for bit in bits {
+        word = (word << 1) | u32::from(bit);
+        used += 1;
+        if used == width {
+            push(word);
+            word = 0;
+            used = 0;
+        }
+    }
+    if used > 0 {
+        push(word << (width - used));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Encoder;
+    use crate::{gamma, Led, StripType};
+
+    /// 55 us at 2.4 MHz is 132 bits.
+    const RESET: [u8; 17] = [0; 17];
+
+    fn with_reset(data: &[u8]) -> Vec<u8> {
+        let mut bytes = data.to_vec();
+        bytes.extend_from_slice(&RESET);
+        bytes
+    }
+
+    #[test]
+    fn bits() {
+        let encoder = Encoder::new(StripType::Rgb);
+        let bits: Vec<bool> = encoder.bits(&[Led::new(0, 0xa5, 0, 0x01)]).collect();
+        let mut expected = vec![true, false, true, false, false, true, false, true];
+        expected.extend_from_slice(&[false; 8]);
+        expected.extend_from_slice(&[false, false, false, false, false, false, false, true]);
+        assert_eq!(bits, expected);
+        assert_eq!(encoder.bits_per_led(), 24);
+        assert_eq!(Encoder::new(StripType::Bgrw).bits_per_led(), 32);
+    }
+
+    #[test]
+    fn color_order() {
+        let led = Led::new(4, 1, 2, 3);
+        let bytes =
+            |strip_type| -> Vec<u8> { Encoder::new(strip_type).color_bytes(&[led]).collect() };
+        assert_eq!(bytes(StripType::Rgb), [1, 2, 3]);
+        assert_eq!(bytes(StripType::Rbg), [1, 3, 2]);
+        assert_eq!(bytes(StripType::Grb), [2, 1, 3]);
+        assert_eq!(bytes(StripType::Gbr), [2, 3, 1]);
+        assert_eq!(bytes(StripType::Brg), [3, 1, 2]);
+        assert_eq!(bytes(StripType::Bgr), [3, 2, 1]);
+        assert_eq!(bytes(StripType::Rgbw), [1, 2, 3, 4]);
+        assert_eq!(bytes(StripType::Grbw), [2, 1, 3, 4]);
+        assert_eq!(bytes(StripType::Bgrw), [3, 2, 1, 4]);
+    }
+
+    #[test]
+    fn brightness_and_gamma() {
+        let mut encoder = Encoder::new(StripType::Rgb);
+        encoder.set_brightness(127);
+        let bytes: Vec<u8> = encoder.color_bytes(&[Led::new(0, 255, 100, 1)]).collect();
+        assert_eq!(bytes, [127, 50, 0]);
+
+        encoder.set_brightness(255);
+        encoder.set_gamma_table(gamma::table_from_factor(2.8));
+        let bytes: Vec<u8> = encoder.color_bytes(&[Led::new(0, 255, 128, 64)]).collect();
+        assert_eq!(bytes, [255, 37, 5]);
+    }
+
+    #[test]
+    fn spi() {
+        let mut out = Vec::new();
+        Encoder::new(StripType::Rgb).encode_spi(&[Led::new(0, 0xff, 0x00, 0x80)], &mut out);
+        #[rustfmt::skip]
+        let expected = with_reset(&[
+            0xdb, 0x6d, 0xb6, // 0xff
+            0x92, 0x49, 0x24, // 0x00
+            0xd2, 0x49, 0x24, // 0x80
+        ]);
+        assert_eq!(out, expected);
+
+        Encoder::new(StripType::Grbw).encode_spi(&[Led::new(0x01, 0xff, 0x00, 0x80)], &mut out);
+        #[rustfmt::skip]
+        let expected = with_reset(&[
+            0x92, 0x49, 0x24, // green 0x00
+            0xdb, 0x6d, 0xb6, // red 0xff
+            0xd2, 0x49, 0x24, // blue 0x80
+            0x92, 0x49, 0x26, // white 0x01
+        ]);
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn spi_inverted() {
+        let mut encoder = Encoder::new(StripType::Rgb);
+        encoder.set_brightness(127);
+        encoder.set_invert(true);
+        let mut out = Vec::new();
+        encoder.encode_spi(&[Led::new(0, 0xff, 0, 0)], &mut out);
+        // 0xff at half brightness is 0x7f. Inverted symbols are 011 for zero and 001 for one.
+        #[rustfmt::skip]
+        let expected = with_reset(&[
+            0x64, 0x92, 0x49, // 0x7f
+            0x6d, 0xb6, 0xdb, // 0x00
+            0x6d, 0xb6, 0xdb, // 0x00
+        ]);
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn pwm() {
+        let mut encoder = Encoder::new(StripType::Rgb);
+        // Inversion is left to the PWM hardware.
+        encoder.set_invert(true);
+        let mut out = Vec::new();
+        encoder.encode_pwm(&[Led::new(0, 0xff, 0x00, 0x80)], &mut out);
+        // 72 data bits and 132 reset bits, padded to 7 words.
+        assert_eq!(out, [0xdb6d_b692, 0x4924_d249, 0x2400_0000, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn reset() {
+        let mut encoder = Encoder::new(StripType::Rgb);
+        assert_eq!(encoder.reset_bits(), 132);
+        // 400 kHz gives 1.2 MHz and a reset of 66 bits, rounded up to 9 bytes.
+        encoder.set_freq(400_000);
+        assert_eq!(encoder.reset_bits(), 66);
+        let mut out = Vec::new();
+        encoder.encode_spi(&[Led::OFF; 2], &mut out);
+        assert_eq!(out.len(), 2 * 9 + 9);
+    }
+}
@@ -1,25 +1,241 @@
-use crate::sys;
+use crate::{sys, ConfigError};
 use std::ffi::CStr;
-use std::fmt;
+use std::{fmt, io};
+
+/// `EMSGSIZE` on Linux, returned by spidev for transfers larger than its buffer.
+const EMSGSIZE: i32 = 90;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
-/// A ws281x error.
-pub struct Error(pub(crate) sys::ws2811_return_t);
+/// A ws281x error. Most variants correspond to one of the error codes in
+/// [`sys::ws2811_return_t`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// Generic failure.
+    Generic,
+    /// Out of memory.
+    OutOfMemory,
+    /// The hardware revision is not supported by the C library.
+    HwNotSupported,
+    /// Memory lock failed.
+    MemLock,
+    /// `mmap()` failed.
+    Mmap,
+    /// Unable to map the peripheral registers.
+    MapRegisters,
+    /// Unable to initialize the GPIO.
+    GpioInit,
+    /// Unable to initialize the PWM hardware.
+    PwmSetup,
+    /// Failed to create the mailbox device.
+    MailboxDevice,
+    /// DMA error.
+    Dma,
+    /// The selected GPIO pin is not possible to use.
+    IllegalGpio,
+    /// Unable to initialize the PCM hardware.
+    PcmSetup,
+    /// Unable to initialize the SPI hardware.
+    SpiSetup,
+    /// An SPI transfer failed.
+    SpiTransfer,
+    /// The configuration was rejected before the hardware was initialized.
+    Config(ConfigError),
+    /// The thread rendering in the background has stopped, so no more frames can be rendered.
+    RenderThreadStopped,
+    /// Opening, configuring or writing to the SPI device used by
+    /// [`SpiController`](crate::SpiController) failed.
+    SpiDevice(io::Error),
+}
 
-impl fmt::Debug for Error {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "ws281x::Error(\"{}\")", self)
+impl Error {
+    /// Converts a return code from the C library into a `Result`.
+    pub(crate) fn check(code: sys::ws2811_return_t) -> Result<()> {
+        use sys::ws2811_return_t::*;
+        Err(match code {
+            WS2811_SUCCESS => return Ok(()),
+            WS2811_ERROR_GENERIC => Error::Generic,
+            WS2811_ERROR_OUT_OF_MEMORY => Error::OutOfMemory,
+            WS2811_ERROR_HW_NOT_SUPPORTED => Error::HwNotSupported,
+            WS2811_ERROR_MEM_LOCK => Error::MemLock,
+            WS2811_ERROR_MMAP => Error::Mmap,
+            WS2811_ERROR_MAP_REGISTERS => Error::MapRegisters,
+            WS2811_ERROR_GPIO_INIT => Error::GpioInit,
+            WS2811_ERROR_PWM_SETUP => Error::PwmSetup,
+            WS2811_ERROR_MAILBOX_DEVICE => Error::MailboxDevice,
+            WS2811_ERROR_DMA => Error::Dma,
+            WS2811_ERROR_ILLEGAL_GPIO => Error::IllegalGpio,
+            WS2811_ERROR_PCM_SETUP => Error::PcmSetup,
+            WS2811_ERROR_SPI_SETUP => Error::SpiSetup,
+            WS2811_ERROR_SPI_TRANSFER => Error::SpiTransfer,
+        })
+    }
+
+    /// Returns the raw C library return code this error corresponds to. Returns `None` for
+    /// errors that did not originate in the C library.
+    pub fn raw_code(&self) -> Option<sys::ws2811_return_t> {
+        use sys::ws2811_return_t::*;
+        Some(match self {
+            Error::Generic => WS2811_ERROR_GENERIC,
+            Error::OutOfMemory => WS2811_ERROR_OUT_OF_MEMORY,
+            Error::HwNotSupported => WS2811_ERROR_HW_NOT_SUPPORTED,
+            Error::MemLock => WS2811_ERROR_MEM_LOCK,
+            Error::Mmap => WS2811_ERROR_MMAP,
+            Error::MapRegisters => WS2811_ERROR_MAP_REGISTERS,
+            Error::GpioInit => WS2811_ERROR_GPIO_INIT,
+            Error::PwmSetup => WS2811_ERROR_PWM_SETUP,
+            Error::MailboxDevice => WS2811_ERROR_MAILBOX_DEVICE,
+            Error::Dma => WS2811_ERROR_DMA,
+            Error::IllegalGpio => WS2811_ERROR_ILLEGAL_GPIO,
+            Error::PcmSetup => WS2811_ERROR_PCM_SETUP,
+            Error::SpiSetup => WS2811_ERROR_SPI_SETUP,
+            Error::SpiTransfer => WS2811_ERROR_SPI_TRANSFER,
+            Error::Config(_) | Error::RenderThreadStopped | Error::SpiDevice(_) => return None,
+        })
+    }
+
+    /// Returns `true` if this error is likely caused by the process lacking the privileges
+    /// needed to access the hardware, and running as root would help.
+    pub fn is_permission_error(&self) -> bool {
+        match self {
+            Error::MemLock | Error::Mmap | Error::MapRegisters | Error::MailboxDevice => true,
+            Error::SpiDevice(error) => error.kind() == io::ErrorKind::PermissionDenied,
+            _ => false,
+        }
+    }
+
+    /// Returns a short human readable suggestion on how the error can be fixed, if there is one.
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            Error::MemLock | Error::Mmap | Error::MapRegisters | Error::MailboxDevice => {
+                Some("access to /dev/mem and /dev/vcio is required, try running as root")
+            }
+            Error::HwNotSupported => {
+                Some("this Raspberry Pi model is not known by the C library, try updating it")
+            }
+            Error::PwmSetup => Some(
+                "the PWM hardware is shared with the onboard analog audio, disable it with \
+                 `dtparam=audio=off` in /boot/config.txt",
+            ),
+            Error::PcmSetup => {
+                Some("the PCM hardware is shared with I2S audio, disable any I2S sound overlays")
+            }
+            Error::IllegalGpio => {
+                Some("use GPIO 12 or 18 for PWM0, 13 or 19 for PWM1, 21 for PCM or 10 for SPI")
+            }
+            Error::Dma => Some(
+                "make sure the DMA channel is valid and not in use by anything else, \
+                 10 is free on most systems",
+            ),
+            Error::SpiSetup => Some(
+                "enable SPI with `dtparam=spi=on` in /boot/config.txt and make sure \
+                 /dev/spidev0.0 is accessible",
+            ),
+            Error::SpiTransfer => Some(
+                "long strips might need a larger SPI buffer, set `spidev.bufsiz` in \
+                 /boot/cmdline.txt",
+            ),
+            Error::SpiDevice(error) if error.kind() == io::ErrorKind::PermissionDenied => Some(
+                "add the user to the `spi` group to get access to /dev/spidev0.0, or run as root",
+            ),
+            Error::SpiDevice(error) if error.kind() == io::ErrorKind::NotFound => {
+                Some("enable SPI with `dtparam=spi=on` in /boot/config.txt")
+            }
+            Error::SpiDevice(error) if error.raw_os_error() == Some(EMSGSIZE) => Some(
+                "long strips might need a larger SPI buffer, set `spidev.bufsiz` in \
+                 /boot/cmdline.txt",
+            ),
+            Error::Generic
+            | Error::OutOfMemory
+            | Error::GpioInit
+            | Error::Config(_)
+            | Error::RenderThreadStopped
+            | Error::SpiDevice(_) => None,
+        }
     }
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let char_ptr = unsafe { sys::ws2811_get_return_t_str(self.0) };
+        match self {
+            Error::Config(error) => return error.fmt(f),
+            Error::RenderThreadStopped => return "The render thread has stopped".fmt(f),
+            Error::SpiDevice(error) => return write!(f, "SPI device error: {}", error),
+            _ => {}
+        }
+        let code = self
+            .raw_code()
+            .unwrap_or(sys::ws2811_return_t::WS2811_ERROR_GENERIC);
+        let char_ptr = unsafe { sys::ws2811_get_return_t_str(code) };
         // SAFETY: We trust that the C library returns a valid string pointer
         let msg = unsafe { CStr::from_ptr(char_ptr) }.to_string_lossy();
         msg.fmt(f)
     }
 }
 
-impl std::error::Error for Error {}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Config(error) => Some(error),
+            Error::SpiDevice(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<ConfigError> for Error {
+    fn from(error: ConfigError) -> Self {
+        Error::Config(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Error;
+    use crate::sys::ws2811_return_t::*;
+    use std::io;
+
+    #[test]
+    fn raw_code_round_trip() {
+        let codes = [
+            WS2811_ERROR_GENERIC,
+            WS2811_ERROR_OUT_OF_MEMORY,
+            WS2811_ERROR_HW_NOT_SUPPORTED,
+            WS2811_ERROR_MEM_LOCK,
+            WS2811_ERROR_MMAP,
+            WS2811_ERROR_MAP_REGISTERS,
+            WS2811_ERROR_GPIO_INIT,
+            WS2811_ERROR_PWM_SETUP,
+            WS2811_ERROR_MAILBOX_DEVICE,
+            WS2811_ERROR_DMA,
+            WS2811_ERROR_ILLEGAL_GPIO,
+            WS2811_ERROR_PCM_SETUP,
+            WS2811_ERROR_SPI_SETUP,
+            WS2811_ERROR_SPI_TRANSFER,
+        ];
+        for code in codes.iter().copied() {
+            let error = Error::check(code).unwrap_err();
+            assert_eq!(error.raw_code(), Some(code));
+        }
+        assert!(Error::check(WS2811_SUCCESS).is_ok());
+    }
+
+    #[test]
+    fn permission_errors() {
+        assert!(Error::Mmap.is_permission_error());
+        assert!(Error::MailboxDevice.is_permission_error());
+        assert!(!Error::HwNotSupported.is_permission_error());
+        assert!(!Error::IllegalGpio.is_permission_error());
+
+        // EACCES and EPERM on the SPI device.
+        for code in [13, 1] {
+            let error = Error::SpiDevice(io::Error::from_raw_os_error(code));
+            assert!(error.is_permission_error());
+            assert!(error.hint().unwrap().contains("`spi` group"));
+        }
+        let not_found = Error::SpiDevice(io::Error::from_raw_os_error(2));
+        assert!(!not_found.is_permission_error());
+        assert!(not_found.hint().unwrap().contains("dtparam=spi=on"));
+    }
+}
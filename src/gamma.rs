@@ -0,0 +1,40 @@
+/// A lookup table mapping every possible channel brightness value to a gamma corrected one.
+pub type GammaTable = [u8; 256];
+
+/// Computes a gamma correction table for the given gamma factor. Uses the same formula as
+/// `ws2811_set_custom_gamma_factor` in the C library. A factor of zero or less gives the
+/// identity table, meaning no correction.
+pub(crate) fn table_from_factor(gamma_factor: f64) -> GammaTable {
+    let mut table = [0; 256];
+    for (i, value) in table.iter_mut().enumerate() {
+        *value = if gamma_factor > 0.0 {
+            ((i as f64 / 255.0).powf(gamma_factor) * 255.0 + 0.5) as u8
+        } else {
+            i as u8
+        };
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::table_from_factor;
+
+    #[test]
+    fn identity() {
+        let table = table_from_factor(1.0);
+        assert!(table.iter().enumerate().all(|(i, &v)| usize::from(v) == i));
+        assert_eq!(table_from_factor(0.0), table);
+        assert_eq!(table_from_factor(-2.0), table);
+    }
+
+    #[test]
+    fn gamma_2_8() {
+        let table = table_from_factor(2.8);
+        assert_eq!(table[0], 0);
+        assert_eq!(table[64], 5);
+        assert_eq!(table[128], 37);
+        assert_eq!(table[255], 255);
+        assert!(table.windows(2).all(|w| w[0] <= w[1]));
+    }
+}
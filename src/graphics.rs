@@ -0,0 +1,107 @@
+use crate::{Led, LedBuffer, Matrix};
+use embedded_graphics_core::draw_target::DrawTarget;
+use embedded_graphics_core::geometry::{OriginDimensions, Size};
+use embedded_graphics_core::pixelcolor::{Rgb888, RgbColor};
+use embedded_graphics_core::Pixel;
+use std::convert::{Infallible, TryFrom};
+
+impl From<Rgb888> for Led {
+    /// Converts to an [`Led`] with the white channel off.
+    fn from(color: Rgb888) -> Self {
+        Led::new(0, color.r(), color.g(), color.b())
+    }
+}
+
+impl<B: LedBuffer> OriginDimensions for Matrix<B> {
+    fn size(&self) -> Size {
+        let dimension = |len: usize| u32::try_from(len).unwrap_or(u32::MAX);
+        Size::new(dimension(self.width()), dimension(self.height()))
+    }
+}
+
+/// Draws on the LEDs of the matrix. Pixels outside of the matrix are ignored. Nothing is shown
+/// until the buffer the matrix is over gets rendered.
+///
+/// # Example
+///
+/// ```
+/// # use rpi_ws281x::{Channel, Controller, Led, LedDriver, Matrix, MatrixLayout};
+/// use embedded_graphics::pixelcolor::Rgb888;
+/// use embedded_graphics::prelude::*;
+/// use embedded_graphics::primitives::{Line, PrimitiveStyle};
+///
+/// let mut controller = Controller::builder(10)
+///     .channel(Channel::builder(18, 64).build())
+///     .build_simulated();
+/// let mut matrix = Matrix::new(MatrixLayout::new(8, 8), controller.buffer(0));
+///
+/// Line::new(Point::new(0, 0), Point::new(7, 7))
+///     .into_styled(PrimitiveStyle::with_stroke(Rgb888::RED, 1))
+///     .draw(&mut matrix)
+///     .unwrap();
+/// assert_eq!(matrix.get(3, 3), Some(Led::RED));
+/// ```
+impl<B: LedBuffer> DrawTarget for Matrix<B> {
+    type Color = Rgb888;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            if let (Ok(x), Ok(y)) = (usize::try_from(point.x), usize::try_from(point.y)) {
+                if let Some(led) = self.get_mut(x, y) {
+                    *led = Led::from(color);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.fill(Led::from(color));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Led, Matrix, MatrixLayout, Rotation};
+    use embedded_graphics::pixelcolor::Rgb888;
+    use embedded_graphics::prelude::*;
+    use embedded_graphics::primitives::{PrimitiveStyle, Rectangle};
+
+    #[test]
+    fn draws_through_layout() {
+        let layout = MatrixLayout::new(2, 3).rotate(Rotation::Deg90);
+        let mut matrix = Matrix::new(layout, vec![Led::OFF; 6]);
+        assert_eq!(matrix.size(), Size::new(3, 2));
+
+        // Partly outside of the matrix.
+        Rectangle::new(Point::new(2, -1), Size::new(4, 2))
+            .into_styled(PrimitiveStyle::with_fill(Rgb888::new(1, 2, 3)))
+            .draw(&mut matrix)
+            .unwrap();
+        let color = Led::new(0, 1, 2, 3);
+        assert_eq!(
+            matrix.row(0).collect::<Vec<_>>(),
+            [Led::OFF, Led::OFF, color]
+        );
+        assert_eq!(matrix.row(1).collect::<Vec<_>>(), [Led::OFF; 3]);
+        assert_eq!(
+            matrix.into_inner(),
+            [Led::OFF, Led::OFF, Led::OFF, Led::OFF, Led::OFF, color]
+        );
+    }
+
+    #[test]
+    fn clear() {
+        let mut matrix = Matrix::new(MatrixLayout::new(2, 2), vec![Led::OFF; 5]);
+        matrix.clear(Rgb888::BLUE).unwrap();
+        assert_eq!(
+            matrix.into_inner(),
+            [Led::BLUE, Led::BLUE, Led::BLUE, Led::BLUE, Led::OFF]
+        );
+    }
+}
@@ -0,0 +1,215 @@
+use crate::sys;
+use std::ffi::CStr;
+
+/// Set in revision codes using the new style format, introduced with the Raspberry Pi 2.
+const NEW_STYLE_FLAG: u32 = 1 << 23;
+
+/// Information about the Raspberry Pi the library is running on, as detected by the C library
+/// during initialization. See [`Controller::hardware_info`](crate::Controller::hardware_info).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HardwareInfo {
+    model: BoardModel,
+    revision: u32,
+    periph_base: u32,
+    videocore_base: u32,
+    description: String,
+}
+
+impl HardwareInfo {
+    /// Reads the information out of the hardware description filled in by the C library.
+    pub(crate) fn from_raw(raw: &sys::rpi_hw_t) -> Self {
+        let description = if raw.desc.is_null() {
+            String::new()
+        } else {
+            // SAFETY: The C library points `desc` at a static, nul terminated string.
+            unsafe { CStr::from_ptr(raw.desc) }
+                .to_string_lossy()
+                .into_owned()
+        };
+        Self {
+            model: BoardModel::from_revision(raw.hwver),
+            revision: raw.hwver,
+            periph_base: raw.periph_base,
+            videocore_base: raw.videocore_base,
+            description,
+        }
+    }
+
+    /// Returns the board model, decoded from the revision code.
+    pub fn model(&self) -> BoardModel {
+        self.model
+    }
+
+    /// Returns the raw revision code of the board, as found in `/proc/cpuinfo`.
+    pub fn revision(&self) -> u32 {
+        self.revision
+    }
+
+    /// Returns the physical base address of the peripherals, as seen by the CPU.
+    pub fn periph_base(&self) -> u32 {
+        self.periph_base
+    }
+
+    /// Returns the base address of the peripherals, as seen by the VideoCore and DMA engine.
+    pub fn videocore_base(&self) -> u32 {
+        self.videocore_base
+    }
+
+    /// Returns the C library's human readable description of the board,
+    /// e.g. `"Pi 4 Model B - 4GB v1.1"`.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+/// Raspberry Pi board models.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum BoardModel {
+    /// Raspberry Pi Model A.
+    A,
+    /// Raspberry Pi Model B.
+    B,
+    /// Raspberry Pi Model A+.
+    APlus,
+    /// Raspberry Pi Model B+.
+    BPlus,
+    /// Raspberry Pi 2 Model B.
+    Pi2B,
+    /// Raspberry Pi Alpha, a prototype.
+    Alpha,
+    /// Compute Module 1.
+    ComputeModule1,
+    /// Raspberry Pi 3 Model B.
+    Pi3B,
+    /// Raspberry Pi Zero.
+    Zero,
+    /// Compute Module 3.
+    ComputeModule3,
+    /// Raspberry Pi Zero W.
+    ZeroW,
+    /// Raspberry Pi 3 Model B+.
+    Pi3BPlus,
+    /// Raspberry Pi 3 Model A+.
+    Pi3APlus,
+    /// Compute Module 3+.
+    ComputeModule3Plus,
+    /// Raspberry Pi 4 Model B.
+    Pi4B,
+    /// Raspberry Pi Zero 2 W.
+    Zero2W,
+    /// Raspberry Pi 400.
+    Pi400,
+    /// Compute Module 4.
+    ComputeModule4,
+    /// Compute Module 4S.
+    ComputeModule4S,
+    /// Raspberry Pi 5.
+    Pi5,
+    /// Compute Module 5.
+    ComputeModule5,
+    /// Raspberry Pi 500.
+    Pi500,
+    /// Compute Module 5 Lite.
+    ComputeModule5Lite,
+    /// A revision code not known to this library.
+    Unknown,
+}
+
+impl BoardModel {
+    /// Decodes the board model from a revision code, as found in `/proc/cpuinfo`.
+    /// Both the old style and the new style format are supported.
+    pub fn from_revision(revision: u32) -> Self {
+        use BoardModel::*;
+        if revision & NEW_STYLE_FLAG != 0 {
+            match (revision >> 4) & 0xff {
+                0x00 => A,
+                0x01 => B,
+                0x02 => APlus,
+                0x03 => BPlus,
+                0x04 => Pi2B,
+                0x05 => Alpha,
+                0x06 => ComputeModule1,
+                0x08 => Pi3B,
+                0x09 => Zero,
+                0x0a => ComputeModule3,
+                0x0c => ZeroW,
+                0x0d => Pi3BPlus,
+                0x0e => Pi3APlus,
+                0x10 => ComputeModule3Plus,
+                0x11 => Pi4B,
+                0x12 => Zero2W,
+                0x13 => Pi400,
+                0x14 => ComputeModule4,
+                0x15 => ComputeModule4S,
+                0x17 => Pi5,
+                0x18 => ComputeModule5,
+                0x19 => Pi500,
+                0x1a => ComputeModule5Lite,
+                _ => Unknown,
+            }
+        } else {
+            // Old style codes are plain numbers. The top bits can hold overvoltage and
+            // warranty flags.
+            match revision & 0xffff {
+                0x0002..=0x0006 | 0x000d..=0x000f => B,
+                0x0007..=0x0009 => A,
+                0x0010 | 0x0013 => BPlus,
+                0x0011 | 0x0014 => ComputeModule1,
+                0x0012 | 0x0015 => APlus,
+                _ => Unknown,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BoardModel, HardwareInfo};
+    use crate::sys;
+
+    #[test]
+    fn new_style_revisions() {
+        assert_eq!(BoardModel::from_revision(0xa02082), BoardModel::Pi3B);
+        assert_eq!(BoardModel::from_revision(0xa020d3), BoardModel::Pi3BPlus);
+        assert_eq!(BoardModel::from_revision(0xc03111), BoardModel::Pi4B);
+        assert_eq!(BoardModel::from_revision(0x902120), BoardModel::Zero2W);
+        assert_eq!(BoardModel::from_revision(0xa01041), BoardModel::Pi2B);
+        assert_eq!(BoardModel::from_revision(0xd04170), BoardModel::Pi5);
+        // Warranty bit set.
+        assert_eq!(BoardModel::from_revision(0x2a02082), BoardModel::Pi3B);
+        assert_eq!(BoardModel::from_revision(0xa020f0), BoardModel::Unknown);
+    }
+
+    #[test]
+    fn old_style_revisions() {
+        assert_eq!(BoardModel::from_revision(0x0002), BoardModel::B);
+        assert_eq!(BoardModel::from_revision(0x0008), BoardModel::A);
+        assert_eq!(BoardModel::from_revision(0x0010), BoardModel::BPlus);
+        assert_eq!(BoardModel::from_revision(0x0012), BoardModel::APlus);
+        assert_eq!(
+            BoardModel::from_revision(0x0014),
+            BoardModel::ComputeModule1
+        );
+        // Overvoltage flag set.
+        assert_eq!(BoardModel::from_revision(0x1000_000e), BoardModel::B);
+        assert_eq!(BoardModel::from_revision(0x0001), BoardModel::Unknown);
+    }
+
+    #[test]
+    fn from_raw() {
+        let raw = sys::rpi_hw_t {
+            type_: 3,
+            hwver: 0xc03111,
+            periph_base: 0xfe00_0000,
+            videocore_base: 0xc000_0000,
+            desc: b"Pi 4 Model B - 4GB v1.1\0".as_ptr() as *mut _,
+        };
+        let info = HardwareInfo::from_raw(&raw);
+        assert_eq!(info.model(), BoardModel::Pi4B);
+        assert_eq!(info.revision(), 0xc03111);
+        assert_eq!(info.periph_base(), 0xfe00_0000);
+        assert_eq!(info.videocore_base(), 0xc000_0000);
+        assert_eq!(info.description(), "Pi 4 Model B - 4GB v1.1");
+    }
+}
@@ -0,0 +1,76 @@
+use crate::{Led, VirtualStripView};
+
+/// Something that holds LED values addressed by a single index, like a channel buffer or a
+/// [`VirtualStripView`]. Lets layouts such as [`Matrix`](crate::Matrix) work on any of them.
+pub trait LedBuffer {
+    /// Returns the number of LEDs in the buffer.
+    fn len(&self) -> usize;
+
+    /// Returns `true` if the buffer has no LEDs.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the LED with the given index, or `None` if it is out of bounds.
+    fn get(&self, index: usize) -> Option<Led>;
+
+    /// Returns a mutable reference to the LED with the given index, or `None` if it is out of
+    /// bounds.
+    fn get_mut(&mut self, index: usize) -> Option<&mut Led>;
+}
+
+impl LedBuffer for [Led] {
+    fn len(&self) -> usize {
+        <[Led]>::len(self)
+    }
+
+    fn get(&self, index: usize) -> Option<Led> {
+        <[Led]>::get(self, index).copied()
+    }
+
+    fn get_mut(&mut self, index: usize) -> Option<&mut Led> {
+        <[Led]>::get_mut(self, index)
+    }
+}
+
+impl LedBuffer for Vec<Led> {
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn get(&self, index: usize) -> Option<Led> {
+        <[Led]>::get(self, index).copied()
+    }
+
+    fn get_mut(&mut self, index: usize) -> Option<&mut Led> {
+        <[Led]>::get_mut(self, index)
+    }
+}
+
+impl LedBuffer for VirtualStripView<'_> {
+    fn len(&self) -> usize {
+        VirtualStripView::len(self)
+    }
+
+    fn get(&self, index: usize) -> Option<Led> {
+        VirtualStripView::get(self, index)
+    }
+
+    fn get_mut(&mut self, index: usize) -> Option<&mut Led> {
+        VirtualStripView::get_mut(self, index)
+    }
+}
+
+impl<B: LedBuffer + ?Sized> LedBuffer for &mut B {
+    fn len(&self) -> usize {
+        (**self).len()
+    }
+
+    fn get(&self, index: usize) -> Option<Led> {
+        (**self).get(index)
+    }
+
+    fn get_mut(&mut self, index: usize) -> Option<&mut Led> {
+        (**self).get_mut(index)
+    }
+}
@@ -1,64 +1,229 @@
 use std::convert::TryFrom;
-use std::mem;
 use std::os::raw::c_int;
 use std::ptr;
+use std::time::Duration;
 
 /// Re-export of the low level bindings to `rpi_ws281x`.
 pub use rpi_ws281x_sys as sys;
 
+#[cfg(feature = "async")]
+mod asynchronous;
+#[cfg(feature = "async")]
+pub use asynchronous::AsyncController;
+
+mod blend;
+pub use blend::BlendMode;
+
+mod color;
+
+mod dither;
+pub use dither::{Dither, Led16};
+
+mod driver;
+pub use driver::LedDriver;
+
+mod driver_thread;
+
+mod effect;
+pub use effect::Effect;
+
+pub mod effects;
+
+mod encoder;
+pub use encoder::Encoder;
+
 mod error;
 pub use error::{Error, Result};
 
+mod gamma;
+pub use gamma::GammaTable;
+
+#[cfg(feature = "embedded-graphics")]
+mod graphics;
+
+mod hardware;
+pub use hardware::{BoardModel, HardwareInfo};
+
 mod led;
 pub use led::Led;
 
+mod led_buffer;
+pub use led_buffer::LedBuffer;
+
+mod matrix;
+pub use matrix::{Matrix, MatrixLayout, Rotation, WiringOrder};
+
+mod render_loop;
+pub use render_loop::{FrameInfo, RenderLoop};
+
+mod rgbw;
+pub use rgbw::WhiteExtraction;
+
+mod simulated;
+pub use simulated::{RenderedFrame, SimulatedController};
+
+#[cfg(feature = "smart-leds")]
+mod smart_leds;
+#[cfg(feature = "smart-leds")]
+pub use smart_leds::SmartLedsChannel;
+
+mod spi;
+pub use spi::SpiController;
+
+mod strip;
+pub use strip::Strip;
+
 mod strip_type;
 pub use strip_type::{InvalidStripTypeError, StripType};
 
+mod threaded;
+pub use threaded::ThreadedController;
+
+mod transition;
+pub use transition::{Transition, TransitionCurve};
+
+mod virtual_strip;
+pub use virtual_strip::{Segment, VirtualStrip, VirtualStripView};
+
+mod validate;
+pub use validate::ConfigError;
+
 /// `usize` version of `sys::RPI_PWM_CHANNELS`.
 pub const NUM_CHANNELS: usize = sys::RPI_PWM_CHANNELS as usize;
 
-#[repr(transparent)]
-pub struct ChannelBuilder(sys::ws2811_channel_t);
+/// The time the data line must be held low after a frame for the LEDs to latch it.
+/// Same as `LED_RESET_uS` in the C library.
+const LED_RESET_TIME: Duration = Duration::from_micros(55);
+
+/// Returns the time it takes to send one frame to the given channels at the given frequency,
+/// including the reset time. The channels are sent in parallel.
+fn frame_transfer_time(freq: u32, channels: &[sys::ws2811_channel_t]) -> Duration {
+    if freq == 0 {
+        return LED_RESET_TIME;
+    }
+    let max_bits = channels
+        .iter()
+        .map(|channel| {
+            let has_white = matches!(
+                StripType::from_raw(channel.strip_type as u32),
+                Some(strip_type) if strip_type.has_white()
+            );
+            let bits_per_led = if has_white { 32 } else { 24 };
+            u64::try_from(channel.count).unwrap_or(0) * bits_per_led
+        })
+        .max()
+        .unwrap_or(0);
+    Duration::from_nanos(max_bits * 1_000_000_000 / u64::from(freq)) + LED_RESET_TIME
+}
+
+pub struct ChannelBuilder(Channel);
 
 impl ChannelBuilder {
     /// Creates a new [`ChannelBuilder`] for the given GPIO pin with the given amount of LEDs.
-    pub fn new(gpio_pin: u8, led_count: u16) -> Self {
-        ChannelBuilder(sys::ws2811_channel_t {
-            gpionum: c_int::from(gpio_pin),
-            invert: 0,
-            count: c_int::from(led_count),
-            strip_type: StripType::Gbr.as_raw(),
-            leds: ptr::null_mut(),
-            brightness: 255,
-            wshift: 0,
-            rshift: 0,
-            gshift: 0,
-            bshift: 0,
-            gamma: ptr::null_mut(),
+    ///
+    /// The C library supports at most `i32::MAX` LEDs per channel. Larger counts are rejected
+    /// by [`ControllerBuilder::validate`].
+    pub fn new(gpio_pin: u8, led_count: u32) -> Self {
+        ChannelBuilder(Channel {
+            raw: sys::ws2811_channel_t {
+                gpionum: c_int::from(gpio_pin),
+                invert: 0,
+                count: 0,
+                strip_type: StripType::Gbr.as_raw(),
+                leds: ptr::null_mut(),
+                brightness: 255,
+                wshift: 0,
+                rshift: 0,
+                gshift: 0,
+                bshift: 0,
+                gamma: ptr::null_mut(),
+            },
+            settings: ChannelSettings {
+                led_count,
+                ..ChannelSettings::default()
+            },
         })
     }
 
     /// Sets the type of LED strip. Defaults to `StripType::Gbr`.
     pub fn strip_type(mut self, strip_type: StripType) -> Self {
-        self.0.strip_type = strip_type.as_raw();
+        self.0.raw.strip_type = strip_type.as_raw();
         self
     }
 
     /// Sets if the output IO should be inverted or not. Defaults to `false`.
     pub fn invert(mut self, invert: bool) -> Self {
-        self.0.invert = c_int::from(invert);
+        self.0.raw.invert = c_int::from(invert);
         self
     }
 
     /// Sets the brightness of the channel between 0 and 255. Defaults to full brightness, 255.
     pub fn brightness(mut self, brightness: u8) -> Self {
-        self.0.brightness = brightness;
+        self.0.raw.brightness = brightness;
         self
     }
 
-    pub fn build(self) -> Channel {
-        Channel(self.0)
+    /// Applies gamma correction with the given gamma factor to all LED values before they are
+    /// sent to the strip. A factor of around 2.8 makes brightness ramps look roughly linear to
+    /// the human eye. A factor of zero or less disables gamma correction, which is the default.
+    ///
+    /// Overrides any table set with [`ChannelBuilder::gamma_table`].
+    pub fn gamma_factor(self, gamma_factor: f64) -> Self {
+        self.gamma_table(gamma::table_from_factor(gamma_factor))
+    }
+
+    /// Sets a custom gamma correction table. Every channel value `v` of every LED is replaced
+    /// with `table[v]` before it is sent to the strip. The table is applied after brightness.
+    ///
+    /// Overrides any factor set with [`ChannelBuilder::gamma_factor`].
+    pub fn gamma_table(mut self, table: GammaTable) -> Self {
+        self.0.settings.gamma = Some(Box::new(table));
+        self
+    }
+
+    /// Automatically moves the white part of all RGB colors over to the white channel when
+    /// rendering, using the given strategy. Only makes sense for RGBW strip types. The LED values
+    /// in the buffers are left untouched. Disabled by default.
+    ///
+    /// See [`Led::rgb_to_rgbw`].
+    pub fn white_extraction(mut self, strategy: WhiteExtraction) -> Self {
+        self.0.settings.white_extraction = Some(strategy);
+        self
+    }
+
+    /// Declares that the channel drives a chain of several physical strips, in the given order.
+    /// The strips can have different strip types and be mounted in reverse. The channel buffer
+    /// holds the LEDs of all strips after each other, see [`Controller::strip`].
+    ///
+    /// Replaces the LED count given to [`ChannelBuilder::new`], and any strip type set with
+    /// [`ChannelBuilder::strip_type`]. White extraction is only applied to the strips with a
+    /// white channel.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use rpi_ws281x::{Channel, Strip, StripType};
+    /// let channel = Channel::builder(18, 0)
+    ///     .strips([
+    ///         Strip::new(60, StripType::Grb),
+    ///         Strip::new(30, StripType::Grbw).reverse(true),
+    ///     ])
+    ///     .build();
+    /// ```
+    pub fn strips(mut self, strips: impl IntoIterator<Item = Strip>) -> Self {
+        self.0.settings.strips = strips.into_iter().collect();
+        self
+    }
+
+    pub fn build(mut self) -> Channel {
+        if !self.0.settings.strips.is_empty() {
+            // The LEDs are repacked on the Rust side, see `strip::pack`.
+            self.0.raw.strip_type = strip::CARRIER_STRIP_TYPE.as_raw();
+        }
+        // Counts the C library can not represent are rejected by `validate`, before the C
+        // struct is ever used.
+        self.0.raw.count = c_int::try_from(self.0.settings.raw_len()).unwrap_or(c_int::MAX);
+        self.0
     }
 }
 
@@ -66,12 +231,48 @@ impl ChannelBuilder {
 /// There can be up to `NUM_CHANNELS` `Channel`s on one [`Controller`].
 ///
 /// The channel instance is handed over to [`Builder::channels`].
-#[repr(transparent)]
-pub struct Channel(sys::ws2811_channel_t);
+pub struct Channel {
+    raw: sys::ws2811_channel_t,
+    settings: ChannelSettings,
+}
+
+/// Channel configuration that is handled on the Rust side rather than by the C library.
+#[derive(Default)]
+struct ChannelSettings {
+    gamma: Option<Box<GammaTable>>,
+    white_extraction: Option<WhiteExtraction>,
+    /// The chain of strips on the channel. Empty when the channel is a single strip handled
+    /// entirely by the C library.
+    strips: Vec<Strip>,
+    /// The LED count given to [`ChannelBuilder::new`]. Unused when there are strips.
+    led_count: u32,
+}
+
+impl ChannelSettings {
+    /// Returns the number of LEDs the user sees on the channel. Differs from the count in the C
+    /// struct when the channel has strips.
+    fn channel_len(&self) -> usize {
+        if self.strips.is_empty() {
+            self.led_count as usize
+        } else {
+            strip::total_len(&self.strips)
+        }
+    }
+
+    /// Returns the number of LEDs the C library has to send on the channel. Differs from the
+    /// number the user sees when the channel has strips.
+    fn raw_len(&self) -> u64 {
+        if self.strips.is_empty() {
+            u64::from(self.led_count)
+        } else {
+            strip::packed_len(&self.strips)
+        }
+    }
+}
 
 impl Channel {
     /// Creates a new [`ChannelBuilder`] for the given GPIO pin with the given amount of LEDs.
-    pub fn builder(gpio_pin: u8, led_count: u16) -> ChannelBuilder {
+    pub fn builder(gpio_pin: u8, led_count: u32) -> ChannelBuilder {
         ChannelBuilder::new(gpio_pin, led_count)
     }
 
@@ -89,19 +290,22 @@ impl Channel {
     /// # Ok(()) }
     /// ```
     pub fn disabled() -> Self {
-        Self(sys::ws2811_channel_t {
-            gpionum: 0,
-            invert: 0,
-            count: 0,
-            strip_type: 0,
-            leds: ptr::null_mut(),
-            brightness: 0,
-            wshift: 0,
-            rshift: 0,
-            gshift: 0,
-            bshift: 0,
-            gamma: ptr::null_mut(),
-        })
+        Self {
+            raw: sys::ws2811_channel_t {
+                gpionum: 0,
+                invert: 0,
+                count: 0,
+                strip_type: 0,
+                leds: ptr::null_mut(),
+                brightness: 0,
+                wshift: 0,
+                rshift: 0,
+                gshift: 0,
+                bshift: 0,
+                gamma: ptr::null_mut(),
+            },
+            settings: ChannelSettings::default(),
+        }
     }
 
     /// Creates a `Channel` directly from the underlying C struct. This is highly unsafe and
@@ -111,36 +315,53 @@ impl Channel {
     ///
     /// `channel` must be correctly set up. See C library for implementation.
     pub unsafe fn from_raw(channel: sys::ws2811_channel_t) -> Self {
-        Self(channel)
+        Self {
+            settings: ChannelSettings {
+                led_count: u32::try_from(channel.count).unwrap_or(0),
+                ..ChannelSettings::default()
+            },
+            raw: channel,
+        }
     }
 }
 
+/// Converts the channel into the underlying C struct. Settings that are handled on the Rust
+/// side, such as a gamma table, are not part of the C struct and are lost in the conversion.
 impl From<Channel> for sys::ws2811_channel_t {
     fn from(channel: Channel) -> sys::ws2811_channel_t {
-        channel.0
+        channel.raw
     }
 }
 
 /// A builder for [`Controller`] structs. Sets up and initializes the hardware for controlling the
 /// LEDs and returns a controller that is then used for actually rendering anything to the LEDs.
-#[repr(transparent)]
-pub struct ControllerBuilder(sys::ws2811_t);
+pub struct ControllerBuilder {
+    raw: sys::ws2811_t,
+    settings: [ChannelSettings; NUM_CHANNELS],
+    allow_dangerous_dma: bool,
+}
 
 impl ControllerBuilder {
     /// Creates a new [`Controller`] builder using the given DMA channel.
     ///
     /// Take care to use a free DMA channel. Selecting one that is already in use might interfere
     /// with other hardware and for example corrupt your SD card. This code cannot recommend
-    /// a safe default since that depends on the hardware/firmware and OS version.
+    /// a safe default since that depends on the hardware/firmware and OS version. Channels known
+    /// to be used on common systems are rejected by [`ControllerBuilder::build`], unless
+    /// [`ControllerBuilder::allow_dangerous_dma`] is called.
     pub fn new(dma_channel: u8) -> Self {
-        Self(sys::ws2811_t {
-            render_wait_time: 0,
-            device: ptr::null_mut(),
-            rpi_hw: ptr::null(),
-            freq: sys::WS2811_TARGET_FREQ,
-            dmanum: i32::from(dma_channel),
-            channel: [Channel::disabled().0, Channel::disabled().0],
-        })
+        Self {
+            raw: sys::ws2811_t {
+                render_wait_time: 0,
+                device: ptr::null_mut(),
+                rpi_hw: ptr::null(),
+                freq: sys::WS2811_TARGET_FREQ,
+                dmanum: i32::from(dma_channel),
+                channel: [Channel::disabled().raw, Channel::disabled().raw],
+            },
+            settings: Default::default(),
+            allow_dangerous_dma: false,
+        }
     }
 
     /// Creates a `ControllerBuilder` directly from the underlying C struct.
@@ -152,46 +373,132 @@ impl ControllerBuilder {
     ///
     /// `controller` must be correctly set up. See C library for implementation.
     pub unsafe fn from_raw(controller: sys::ws2811_t) -> Self {
-        Self(controller)
+        Self {
+            raw: controller,
+            settings: Default::default(),
+            allow_dangerous_dma: false,
+        }
     }
 
     /// Sets the frequency in Hz that the controller will output data at.
     pub fn freq(mut self, freq: u32) -> Self {
-        self.0.freq = freq;
+        self.raw.freq = freq;
+        self
+    }
+
+    /// Allows DMA channels that are known to be used by the firmware or kernel on some systems.
+    /// Only use this if you know the channel is free on yours.
+    pub fn allow_dangerous_dma(mut self) -> Self {
+        self.allow_dangerous_dma = true;
         self
     }
 
     /// Sets the channel first on the controller. More convenient to call than
     /// [`ControllerBuilder::channels`] for use cases with only one LED strip.
     pub fn channel(mut self, channel: Channel) -> Self {
-        self.0.channel[0] = channel.0;
+        self.raw.channel[0] = channel.raw;
+        self.settings[0] = channel.settings;
         self
     }
 
     /// Sets all channels on the controller.
     pub fn channels(mut self, channels: [Channel; NUM_CHANNELS]) -> Self {
-        // This transmute is safe because `Channel` is a newtype with `#[repr(transparent)]`.
-        self.0.channel = unsafe { mem::transmute(channels) };
+        let [channel0, channel1] = channels;
+        self.raw.channel = [channel0.raw, channel1.raw];
+        self.settings = [channel0.settings, channel1.settings];
         self
     }
 
+    /// Checks that the GPIO pins, DMA channel and frequency are supported by the hardware,
+    /// without initializing anything. Called by [`ControllerBuilder::build`].
+    pub fn validate(&self) -> std::result::Result<(), ConfigError> {
+        let raw_lens = [self.settings[0].raw_len(), self.settings[1].raw_len()];
+        validate::validate(&self.raw, raw_lens, self.allow_dangerous_dma)
+    }
+
     /// Tries to initialize the hardware to control LEDs in the way the builder is configured.
     /// Returns the [`Controller`] on success.
+    ///
+    /// The configuration is validated first, see [`ControllerBuilder::validate`]. Returns
+    /// [`Error::OutOfMemory`] if a channel has custom gamma correction but the C library did not
+    /// allocate a gamma table for it.
     pub fn build(mut self) -> Result<Controller> {
         assert_eq!(
             usize::try_from(sys::RPI_PWM_CHANNELS).unwrap(),
-            self.0.channel.len()
+            self.raw.channel.len()
         );
-        match unsafe { sys::ws2811_init(&mut self.0) } {
-            sys::ws2811_return_t::WS2811_SUCCESS => Ok(Controller(self.0)),
-            error => Err(Error(error)),
+        self.validate()?;
+        Error::check(unsafe { sys::ws2811_init(&mut self.raw) })?;
+        let strip_buffer =
+            |settings: &ChannelSettings| vec![Led::OFF; strip::total_len(&settings.strips)];
+        // Created right away, so the C library is cleaned up again if anything below fails.
+        let mut controller = Controller {
+            strip_buffers: [
+                strip_buffer(&self.settings[0]),
+                strip_buffer(&self.settings[1]),
+            ],
+            raw: self.raw,
+            settings: self.settings,
+            scratch: Default::default(),
+            dither: Default::default(),
+            dithered: Default::default(),
+        };
+        // The C library allocates a gamma table for every channel during init, and frees it
+        // again in `ws2811_fini`. So custom tables are copied into the library owned memory.
+        let channels = controller.raw.channel.iter_mut().zip(&controller.settings);
+        for (channel, settings) in channels {
+            if let Some(table) = &settings.gamma {
+                if channel.gamma.is_null() {
+                    return Err(Error::OutOfMemory);
+                }
+                unsafe { ptr::copy_nonoverlapping(table.as_ptr(), channel.gamma, table.len()) };
+            }
+        }
+        Ok(controller)
+    }
+
+    /// Builds a [`SpiController`] that drives the strip on channel 0 through the Linux SPI
+    /// driver, in pure Rust. Channel 0 must use GPIO 10 and channel 1 must be disabled. The DMA
+    /// channel is ignored. Does not require root.
+    pub fn build_spi(self) -> Result<SpiController> {
+        validate::validate_spi(&self.raw)?;
+        let [settings, _] = self.settings;
+        SpiController::open(self.raw.freq, &self.raw.channel[0], settings)
+    }
+
+    /// Builds a [`SimulatedController`] with the channel lengths this builder is configured with,
+    /// without touching any hardware. Useful for testing code that drives LEDs on machines that
+    /// are not a Raspberry Pi.
+    pub fn build_simulated(self) -> SimulatedController {
+        let mut controller = SimulatedController::new([
+            self.settings[0].channel_len(),
+            self.settings[1].channel_len(),
+        ]);
+        controller.min_frame_interval = frame_transfer_time(self.raw.freq, &self.raw.channel);
+        for (channel_index, channel) in self.raw.channel.iter().enumerate() {
+            controller.set_brightness(channel_index, channel.brightness);
+            controller.white_extraction[channel_index] =
+                self.settings[channel_index].white_extraction;
+            controller.strips[channel_index] = self.settings[channel_index].strips.clone();
         }
+        controller
     }
 }
 
 /// A ws281x LED controller. Instances of this type are created via the [`Builder`].
-#[repr(transparent)]
-pub struct Controller(sys::ws2811_t);
+pub struct Controller {
+    raw: sys::ws2811_t,
+    settings: [ChannelSettings; NUM_CHANNELS],
+    /// The buffers handed out to the user for channels with strips. The C library buffer then
+    /// holds the repacked LEDs instead.
+    strip_buffers: [Vec<Led>; NUM_CHANNELS],
+    /// Buffers holding the LED values after processing on the Rust side, for channels with
+    /// settings that require it.
+    scratch: [Vec<Led>; NUM_CHANNELS],
+    /// State and output buffers for [`Controller::render_dithered`].
+    dither: [Dither; NUM_CHANNELS],
+    dithered: [Vec<Led>; NUM_CHANNELS],
+}
 
 impl Controller {
     pub fn builder(dma_channel: u8) -> ControllerBuilder {
@@ -208,7 +515,14 @@ impl Controller {
     /// `controller` must be correctly set up and [`sys::ws2811_init`] already called on it.
     /// See C library for implementation.
     pub unsafe fn from_raw(controller: sys::ws2811_t) -> Self {
-        Self(controller)
+        Self {
+            raw: controller,
+            settings: Default::default(),
+            strip_buffers: Default::default(),
+            scratch: Default::default(),
+            dither: Default::default(),
+            dithered: Default::default(),
+        }
     }
 
     /// Returns a mutable slice where all the LED values can be set directly.
@@ -216,23 +530,115 @@ impl Controller {
     /// # Panics
     ///
     /// Panics if `channel_index >= NUM_CHANNELS`.
-    pub fn buffer<'a>(&'a mut self, channel_index: usize) -> &mut [Led] {
-        // This casting to `*mut Led` is safe because Led is a newtype struct over ws2811_led_t
-        // with #[repr(transparent])].
-        let leds_ptr: *mut Led = self.0.channel[channel_index].leds as *mut Led;
-        let count = usize::try_from(self.0.channel[channel_index].count).unwrap();
+    pub fn buffer<'a>(&'a mut self, channel_index: usize) -> &'a mut [Led] {
+        let leds_ptr = self.buffer_ptr(channel_index);
+        let count = self.channel_len(channel_index);
         // SAFETY: We trust the C library to have initialized the leds ptr and count correctly.
         unsafe { std::slice::from_raw_parts_mut::<'a, Led>(leds_ptr, count) }
     }
 
+    /// Returns mutable slices where the LED values of all channels can be set directly.
+    pub fn buffers(&mut self) -> [&mut [Led]; NUM_CHANNELS] {
+        let ptrs = [self.buffer_ptr(0), self.buffer_ptr(1)];
+        // SAFETY: Same as in `buffer`. The C library allocates separate buffers for each channel,
+        // so the slices do not overlap.
+        unsafe {
+            [
+                std::slice::from_raw_parts_mut(ptrs[0], self.channel_len(0)),
+                std::slice::from_raw_parts_mut(ptrs[1], self.channel_len(1)),
+            ]
+        }
+    }
+
+    /// Returns a pointer to the start of the buffer handed out for the given channel.
+    fn buffer_ptr(&mut self, channel_index: usize) -> *mut Led {
+        if self.settings[channel_index].strips.is_empty() {
+            // This casting to `*mut Led` is safe because Led is a newtype struct over
+            // ws2811_led_t with #[repr(transparent])].
+            self.raw.channel[channel_index].leds as *mut Led
+        } else {
+            self.strip_buffers[channel_index].as_mut_ptr()
+        }
+    }
+
+    /// Returns the number of LEDs on the channel with the given index. For channels with
+    /// several strips, this is the total number of LEDs on all of them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel_index >= NUM_CHANNELS`.
+    pub fn channel_len(&self, channel_index: usize) -> usize {
+        self.settings[channel_index].channel_len()
+    }
+
+    /// Returns the number of strips on the channel with the given index. A channel that was not
+    /// configured with [`ChannelBuilder::strips`] counts as a single strip.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel_index >= NUM_CHANNELS`.
+    pub fn strip_count(&self, channel_index: usize) -> usize {
+        self.settings[channel_index].strips.len().max(1)
+    }
+
+    /// Returns the part of the channel buffer that holds the LEDs of one strip, in logical
+    /// order, even for strips that are mounted in reverse.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel_index >= NUM_CHANNELS` or `strip_index` is out of bounds for the
+    /// channel.
+    pub fn strip(&mut self, channel_index: usize, strip_index: usize) -> &mut [Led] {
+        let range = strip::strip_range(
+            &self.settings[channel_index].strips,
+            self.channel_len(channel_index),
+            strip_index,
+        );
+        &mut self.buffer(channel_index)[range]
+    }
+
+    /// Returns the time it takes to send one frame to the LEDs, given the number of LEDs, the
+    /// strip types and the frequency. Rendering more often than this is not possible.
+    pub fn min_frame_interval(&self) -> Duration {
+        frame_transfer_time(self.raw.freq, &self.raw.channel)
+    }
+
+    /// Returns information about the Raspberry Pi the library detected during initialization.
+    /// Returns `None` if no hardware was detected.
+    pub fn hardware_info(&self) -> Option<HardwareInfo> {
+        // SAFETY: When set, `rpi_hw` points into a static table in the C library.
+        unsafe { self.raw.rpi_hw.as_ref() }.map(HardwareInfo::from_raw)
+    }
+
+    /// Returns the brightness of the channel with the given index, between 0 and 255.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel_index >= NUM_CHANNELS`.
+    pub fn brightness(&self, channel_index: usize) -> u8 {
+        self.raw.channel[channel_index].brightness
+    }
+
+    /// Sets the brightness of the channel with the given index, between 0 and 255.
+    /// Takes effect on the next render. This scales all LED values on the channel without
+    /// having to touch the buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel_index >= NUM_CHANNELS`.
+    pub fn set_brightness(&mut self, channel_index: usize, brightness: u8) {
+        self.raw.channel[channel_index].brightness = brightness;
+    }
+
     /// Render what is currently in the buffers to the LEDs.
     ///
     /// See [`render_buffer`] for a way to supply the buffer and render it in one call.
     pub fn render(&mut self) -> Result<()> {
-        match unsafe { sys::ws2811_render(&mut self.0) } {
-            sys::ws2811_return_t::WS2811_SUCCESS => Ok(()),
-            error => Err(Error(error)),
-        }
+        let leds = [
+            self.buffer_ptr(0) as *mut sys::ws2811_led_t,
+            self.buffer_ptr(1) as *mut sys::ws2811_led_t,
+        ];
+        self.render_leds(leds)
     }
 
     /// Renders the given buffers instead of the buffers held by this [`Controller`] instance.
@@ -244,26 +650,151 @@ impl Controller {
     /// Panics if any of the `&[Led]` slices are not the same length as the corresponding
     /// [`Channel`]s `led_count` as given to the [`Channel`] constructor.
     pub fn render_buffer(&mut self, buffers: [&[Led]; NUM_CHANNELS]) -> Result<()> {
-        let original_leds_ptrs: [*mut sys::ws2811_led_t; NUM_CHANNELS] =
-            [self.0.channel[0].leds, self.0.channel[1].leds];
+        assert_eq!(self.channel_len(0), buffers[0].len());
+        assert_eq!(self.channel_len(1), buffers[1].len());
 
-        assert_eq!(self.0.channel[0].count as usize, buffers[0].len());
-        assert_eq!(self.0.channel[1].count as usize, buffers[1].len());
+        self.render_leds([buffers[0].as_ptr() as *mut _, buffers[1].as_ptr() as *mut _])
+    }
 
-        self.0.channel[0].leds = buffers[0].as_ptr() as *mut _;
-        self.0.channel[1].leds = buffers[1].as_ptr() as *mut _;
+    /// Renders high precision LED values using temporal dithering. The fractional part that
+    /// does not fit in 8 bits is carried over between calls, so over a few frames the average
+    /// output matches the input with full precision. For this to work, this method must be
+    /// called every frame, at a high enough frame rate for the flicker to be invisible.
+    ///
+    /// The channel brightness is applied at 16 bit precision before dithering, instead of by
+    /// the C library. This keeps fades smooth even at low brightness settings. The buffers held by
+    /// this [`Controller`] instance are left untouched.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the `&[Led16]` slices are not the same length as the corresponding
+    /// [`Channel`]s `led_count` as given to the [`Channel`] constructor.
+    pub fn render_dithered(&mut self, buffers: [&[Led16]; NUM_CHANNELS]) -> Result<()> {
+        let original_brightness = [self.brightness(0), self.brightness(1)];
+        for (channel_index, input) in buffers.iter().enumerate() {
+            assert_eq!(self.channel_len(channel_index), input.len());
+            let output = &mut self.dithered[channel_index];
+            output.resize(input.len(), Led::OFF);
+            self.dither[channel_index].dither(input, original_brightness[channel_index], output);
+            // Brightness is already applied, and the C library should not scale it again.
+            self.raw.channel[channel_index].brightness = 255;
+        }
 
-        let render_result = self.render();
+        let leds = [
+            self.dithered[0].as_mut_ptr() as *mut _,
+            self.dithered[1].as_mut_ptr() as *mut _,
+        ];
+        let render_result = self.render_leds(leds);
 
-        self.0.channel[0].leds = original_leds_ptrs[0];
-        self.0.channel[1].leds = original_leds_ptrs[1];
+        self.raw.channel[0].brightness = original_brightness[0];
+        self.raw.channel[1].brightness = original_brightness[1];
 
         render_result
     }
+
+    /// Renders the LED values behind the given pointers. Each pointer must be valid for reading
+    /// as many LEDs as the corresponding channel has.
+    fn render_leds(&mut self, leds: [*mut sys::ws2811_led_t; NUM_CHANNELS]) -> Result<()> {
+        let original_leds_ptrs: [*mut sys::ws2811_led_t; NUM_CHANNELS] =
+            [self.raw.channel[0].leds, self.raw.channel[1].leds];
+
+        for (channel_index, &source) in leds.iter().enumerate() {
+            let count = self.channel_len(channel_index);
+            let channel = &mut self.raw.channel[channel_index];
+            let settings = &self.settings[channel_index];
+            channel.leds = source;
+            if count == 0 {
+                continue;
+            }
+            // SAFETY: The caller guarantees `source` is valid for `count` LEDs.
+            let source = unsafe { std::slice::from_raw_parts(source as *const Led, count) };
+            let scratch = &mut self.scratch[channel_index];
+            if !settings.strips.is_empty() {
+                strip::pack(&settings.strips, source, settings.white_extraction, scratch);
+                channel.leds = scratch.as_mut_ptr() as *mut _;
+            } else if let Some(extraction) = settings.white_extraction {
+                scratch.clear();
+                scratch.extend(source.iter().map(|led| led.rgb_to_rgbw(extraction)));
+                channel.leds = scratch.as_mut_ptr() as *mut _;
+            }
+        }
+
+        let render_result = Error::check(unsafe { sys::ws2811_render(&mut self.raw) });
+
+        self.raw.channel[0].leds = original_leds_ptrs[0];
+        self.raw.channel[1].leds = original_leds_ptrs[1];
+
+        render_result
+    }
+
+    /// Replaces the gamma correction of all channels with a table computed from the given gamma
+    /// factor. Takes effect on the next render. A factor of zero or less disables gamma
+    /// correction. See [`ChannelBuilder::gamma_factor`] for configuring channels individually.
+    pub fn set_gamma_factor(&mut self, gamma_factor: f64) {
+        unsafe { sys::ws2811_set_custom_gamma_factor(&mut self.raw, gamma_factor) };
+    }
+
+    /// Blocks until the DMA transfer of the previously rendered frame has finished.
+    ///
+    /// After this returns, the LEDs show the last rendered frame and it is possible to measure
+    /// the real latency of a frame, or to safely reuse a buffer given to [`render_buffer`].
+    ///
+    /// [`render_buffer`]: Controller::render_buffer
+    pub fn wait(&mut self) -> Result<()> {
+        Error::check(unsafe { sys::ws2811_wait(&mut self.raw) })
+    }
+
+    /// Renders what is currently in the buffers to the LEDs and waits for the DMA transfer
+    /// to finish. Equivalent to calling [`render`] followed by [`wait`].
+    ///
+    /// [`render`]: Controller::render
+    /// [`wait`]: Controller::wait
+    pub fn render_and_wait(&mut self) -> Result<()> {
+        self.render()?;
+        self.wait()
+    }
+}
+
+impl LedDriver for Controller {
+    fn channel_len(&self, channel_index: usize) -> usize {
+        Controller::channel_len(self, channel_index)
+    }
+
+    fn buffer(&mut self, channel_index: usize) -> &mut [Led] {
+        Controller::buffer(self, channel_index)
+    }
+
+    fn buffers(&mut self) -> [&mut [Led]; NUM_CHANNELS] {
+        Controller::buffers(self)
+    }
+
+    fn brightness(&self, channel_index: usize) -> u8 {
+        Controller::brightness(self, channel_index)
+    }
+
+    fn set_brightness(&mut self, channel_index: usize, brightness: u8) {
+        Controller::set_brightness(self, channel_index, brightness)
+    }
+
+    fn render(&mut self) -> Result<()> {
+        Controller::render(self)
+    }
+
+    fn render_buffer(&mut self, buffers: [&[Led]; NUM_CHANNELS]) -> Result<()> {
+        Controller::render_buffer(self, buffers)
+    }
+
+    fn min_frame_interval(&self) -> Duration {
+        Controller::min_frame_interval(self)
+    }
+
+    fn wait(&mut self) -> Result<()> {
+        Controller::wait(self)
+    }
 }
 
 impl Drop for Controller {
     fn drop(&mut self) {
-        unsafe { sys::ws2811_fini(&mut self.0) };
+        unsafe { sys::ws2811_fini(&mut self.raw) };
     }
 }
@@ -0,0 +1,433 @@
+use crate::{Led, LedBuffer};
+
+/// The order the LEDs of a panel are wired in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum WiringOrder {
+    /// The LEDs run along the rows, starting in the top left corner.
+    RowMajor,
+    /// The LEDs run along the columns, starting in the top left corner.
+    ColumnMajor,
+}
+
+/// Clockwise rotation of the image shown on a [`Matrix`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Rotation {
+    /// No rotation.
+    Deg0,
+    /// Rotated 90 degrees clockwise. Swaps the width and height.
+    Deg90,
+    /// Rotated 180 degrees.
+    Deg180,
+    /// Rotated 270 degrees clockwise. Swaps the width and height.
+    Deg270,
+}
+
+/// Describes how the LEDs of a 2D panel, or a grid of identical panels, map to indices in a
+/// buffer. Use with [`Matrix`] to address LEDs by `(x, y)` coordinates.
+///
+/// The layout is described as the panels are wired, seen from the front with the first LED in
+/// the top left corner. [`MatrixLayout::rotate`] and the mirror settings then transform the
+/// coordinates used to draw, so panels mounted sideways or with the data entering in another
+/// corner show an upright image.
+///
+/// # Example
+///
+/// ```
+/// # use rpi_ws281x::{MatrixLayout, WiringOrder};
+/// // A 16x16 panel wired row by row, where every other row runs right to left.
+/// let layout = MatrixLayout::new(16, 16)
+///     .order(WiringOrder::RowMajor)
+///     .serpentine(true);
+/// assert_eq!(layout.index(0, 1), Some(31));
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct MatrixLayout {
+    tile_width: usize,
+    tile_height: usize,
+    order: WiringOrder,
+    serpentine: bool,
+    tiles_x: usize,
+    tiles_y: usize,
+    tile_serpentine: bool,
+    rotation: Rotation,
+    mirror_x: bool,
+    mirror_y: bool,
+}
+
+impl MatrixLayout {
+    /// Creates a layout for a single panel with the given size, wired in row major order without
+    /// serpentine.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            tile_width: width,
+            tile_height: height,
+            order: WiringOrder::RowMajor,
+            serpentine: false,
+            tiles_x: 1,
+            tiles_y: 1,
+            tile_serpentine: false,
+            rotation: Rotation::Deg0,
+            mirror_x: false,
+            mirror_y: false,
+        }
+    }
+
+    /// Sets the order the LEDs of each panel are wired in. Defaults to
+    /// [`WiringOrder::RowMajor`].
+    pub fn order(mut self, order: WiringOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Sets if every other row, or column for [`WiringOrder::ColumnMajor`], runs in the opposite
+    /// direction. This zigzag wiring is the most common one for panels. Defaults to `false`.
+    pub fn serpentine(mut self, serpentine: bool) -> Self {
+        self.serpentine = serpentine;
+        self
+    }
+
+    /// Makes the layout a grid of `columns` by `rows` identical panels, each with the size given
+    /// to [`MatrixLayout::new`]. The panels are chained row by row, starting in the top left.
+    /// Defaults to a single panel.
+    pub fn tiles(mut self, columns: usize, rows: usize) -> Self {
+        self.tiles_x = columns;
+        self.tiles_y = rows;
+        self
+    }
+
+    /// Sets if every other row of panels is chained right to left. Defaults to `false`.
+    pub fn tile_serpentine(mut self, serpentine: bool) -> Self {
+        self.tile_serpentine = serpentine;
+        self
+    }
+
+    /// Rotates the image clockwise. Defaults to [`Rotation::Deg0`].
+    pub fn rotate(mut self, rotation: Rotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Sets if the image is mirrored horizontally, before rotation. Defaults to `false`.
+    pub fn mirror_x(mut self, mirror: bool) -> Self {
+        self.mirror_x = mirror;
+        self
+    }
+
+    /// Sets if the image is mirrored vertically, before rotation. Defaults to `false`.
+    pub fn mirror_y(mut self, mirror: bool) -> Self {
+        self.mirror_y = mirror;
+        self
+    }
+
+    /// Returns the width of the image, after rotation.
+    pub fn width(&self) -> usize {
+        match self.rotation {
+            Rotation::Deg0 | Rotation::Deg180 => self.physical_width(),
+            Rotation::Deg90 | Rotation::Deg270 => self.physical_height(),
+        }
+    }
+
+    /// Returns the height of the image, after rotation.
+    pub fn height(&self) -> usize {
+        match self.rotation {
+            Rotation::Deg0 | Rotation::Deg180 => self.physical_height(),
+            Rotation::Deg90 | Rotation::Deg270 => self.physical_width(),
+        }
+    }
+
+    /// Returns the total number of LEDs in the layout.
+    pub fn len(&self) -> usize {
+        self.physical_width() * self.physical_height()
+    }
+
+    /// Returns `true` if the layout has no LEDs.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the buffer index of the LED at the given coordinates of the image, or `None` if
+    /// the coordinates are outside of it.
+    pub fn index(&self, x: usize, y: usize) -> Option<usize> {
+        let (width, height) = (self.width(), self.height());
+        if x >= width || y >= height {
+            return None;
+        }
+        let x = if self.mirror_x { width - 1 - x } else { x };
+        let y = if self.mirror_y { height - 1 - y } else { y };
+        let (x, y) = match self.rotation {
+            Rotation::Deg0 => (x, y),
+            Rotation::Deg90 => (height - 1 - y, x),
+            Rotation::Deg180 => (width - 1 - x, height - 1 - y),
+            Rotation::Deg270 => (y, width - 1 - x),
+        };
+
+        let (tile_x, local_x) = (x / self.tile_width, x % self.tile_width);
+        let (tile_y, local_y) = (y / self.tile_height, y % self.tile_height);
+        let tile_x = zigzag(tile_x, tile_y, self.tiles_x, self.tile_serpentine);
+        let tile = tile_y * self.tiles_x + tile_x;
+        let local = match self.order {
+            WiringOrder::RowMajor => {
+                let local_x = zigzag(local_x, local_y, self.tile_width, self.serpentine);
+                local_y * self.tile_width + local_x
+            }
+            WiringOrder::ColumnMajor => {
+                let local_y = zigzag(local_y, local_x, self.tile_height, self.serpentine);
+                local_x * self.tile_height + local_y
+            }
+        };
+        Some(tile * self.tile_width * self.tile_height + local)
+    }
+
+    fn physical_width(&self) -> usize {
+        self.tile_width * self.tiles_x
+    }
+
+    fn physical_height(&self) -> usize {
+        self.tile_height * self.tiles_y
+    }
+}
+
+/// Returns the position along a line of `len` LEDs, which runs backwards on odd lines if
+/// `serpentine` is set.
+fn zigzag(position: usize, line: usize, len: usize, serpentine: bool) -> usize {
+    if serpentine && line % 2 == 1 {
+        len - 1 - position
+    } else {
+        position
+    }
+}
+
+/// A 2D view of the LEDs in a [`LedBuffer`], addressed by `(x, y)` coordinates according to a
+/// [`MatrixLayout`]. The origin is in the top left corner.
+///
+/// The buffer is usually a channel buffer. For panels spread over both channels, use a
+/// [`VirtualStripView`](crate::VirtualStripView) that joins them.
+///
+/// # Example
+///
+/// ```
+/// # use rpi_ws281x::{Channel, Controller, Led, LedDriver, Matrix, MatrixLayout};
+/// let mut controller = Controller::builder(10)
+///     .channel(Channel::builder(18, 64).build())
+///     .build_simulated();
+/// let layout = MatrixLayout::new(8, 8).serpentine(true);
+///
+/// let mut matrix = Matrix::new(layout, controller.buffer(0));
+/// matrix.set(7, 1, Led::RED);
+/// assert_eq!(controller.buffer(0)[8], Led::RED);
+/// ```
+#[derive(Debug)]
+pub struct Matrix<B> {
+    layout: MatrixLayout,
+    buffer: B,
+}
+
+impl<B: LedBuffer> Matrix<B> {
+    /// Creates a matrix over the given buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer is shorter than the number of LEDs in the layout.
+    pub fn new(layout: MatrixLayout, buffer: B) -> Self {
+        assert!(
+            buffer.len() >= layout.len(),
+            "buffer with {} LEDs too short for a {}x{} matrix",
+            buffer.len(),
+            layout.width(),
+            layout.height()
+        );
+        Self { layout, buffer }
+    }
+
+    /// Returns the layout of the matrix.
+    pub fn layout(&self) -> &MatrixLayout {
+        &self.layout
+    }
+
+    /// Returns the width of the matrix.
+    pub fn width(&self) -> usize {
+        self.layout.width()
+    }
+
+    /// Returns the height of the matrix.
+    pub fn height(&self) -> usize {
+        self.layout.height()
+    }
+
+    /// Returns the LED at the given coordinates, or `None` if they are outside of the matrix.
+    pub fn get(&self, x: usize, y: usize) -> Option<Led> {
+        self.buffer.get(self.layout.index(x, y)?)
+    }
+
+    /// Returns a mutable reference to the LED at the given coordinates, or `None` if they are
+    /// outside of the matrix.
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut Led> {
+        self.buffer.get_mut(self.layout.index(x, y)?)
+    }
+
+    /// Sets the LED at the given coordinates.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the coordinates are outside of the matrix.
+    pub fn set(&mut self, x: usize, y: usize, led: Led) {
+        let (width, height) = (self.width(), self.height());
+        *self.get_mut(x, y).unwrap_or_else(|| {
+            panic!(
+                "({}, {}) is outside of the {}x{} matrix",
+                x, y, width, height
+            )
+        }) = led;
+    }
+
+    /// Sets all LEDs of the matrix to the given color.
+    pub fn fill(&mut self, led: Led) {
+        for index in 0..self.layout.len() {
+            *self.buffer.get_mut(index).unwrap() = led;
+        }
+    }
+
+    /// Returns an iterator over the LEDs of row `y`, from left to right.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `y >= self.height()`.
+    pub fn row(&self, y: usize) -> impl Iterator<Item = Led> + '_ {
+        assert!(y < self.height(), "row out of bounds");
+        (0..self.width()).map(move |x| self.get(x, y).unwrap())
+    }
+
+    /// Returns an iterator over the LEDs of column `x`, from top to bottom.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x >= self.width()`.
+    pub fn column(&self, x: usize) -> impl Iterator<Item = Led> + '_ {
+        assert!(x < self.width(), "column out of bounds");
+        (0..self.height()).map(move |y| self.get(x, y).unwrap())
+    }
+
+    /// Sets the LEDs of row `y` from left to right. Stops at whichever runs out first, the
+    /// row or the given LEDs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `y >= self.height()`.
+    pub fn set_row(&mut self, y: usize, leds: impl IntoIterator<Item = Led>) {
+        assert!(y < self.height(), "row out of bounds");
+        for (x, led) in (0..self.width()).zip(leds) {
+            self.set(x, y, led);
+        }
+    }
+
+    /// Sets the LEDs of column `x` from top to bottom. Stops at whichever runs out first, the
+    /// column or the given LEDs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `x >= self.width()`.
+    pub fn set_column(&mut self, x: usize, leds: impl IntoIterator<Item = Led>) {
+        assert!(x < self.width(), "column out of bounds");
+        for (y, led) in (0..self.height()).zip(leds) {
+            self.set(x, y, led);
+        }
+    }
+
+    /// Returns the underlying buffer.
+    pub fn into_inner(self) -> B {
+        self.buffer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Matrix, MatrixLayout, Rotation, WiringOrder};
+    use crate::Led;
+
+    /// Returns the buffer indices of all LEDs, row by row.
+    fn indices(layout: MatrixLayout) -> Vec<Vec<usize>> {
+        (0..layout.height())
+            .map(|y| {
+                (0..layout.width())
+                    .map(|x| layout.index(x, y).unwrap())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn wiring_orders() {
+        let layout = MatrixLayout::new(3, 2);
+        assert_eq!(indices(layout), [[0, 1, 2], [3, 4, 5]]);
+        assert_eq!(layout.index(3, 0), None);
+        assert_eq!(layout.index(0, 2), None);
+
+        let serpentine = layout.serpentine(true);
+        assert_eq!(indices(serpentine), [[0, 1, 2], [5, 4, 3]]);
+
+        let columns = layout.order(WiringOrder::ColumnMajor).serpentine(true);
+        assert_eq!(indices(columns), [[0, 3, 4], [1, 2, 5]]);
+    }
+
+    #[test]
+    fn tiles() {
+        let layout = MatrixLayout::new(2, 2).serpentine(true).tiles(2, 2);
+        assert_eq!(layout.len(), 16);
+        assert_eq!(
+            indices(layout),
+            [[0, 1, 4, 5], [3, 2, 7, 6], [8, 9, 12, 13], [11, 10, 15, 14]]
+        );
+        assert_eq!(
+            indices(layout.tile_serpentine(true)),
+            [[0, 1, 4, 5], [3, 2, 7, 6], [12, 13, 8, 9], [15, 14, 11, 10]]
+        );
+    }
+
+    #[test]
+    fn rotation_and_mirroring() {
+        let layout = MatrixLayout::new(3, 2);
+        let rotated = layout.rotate(Rotation::Deg90);
+        assert_eq!((rotated.width(), rotated.height()), (2, 3));
+        assert_eq!(indices(rotated), [[2, 5], [1, 4], [0, 3]]);
+        assert_eq!(
+            indices(layout.rotate(Rotation::Deg180)),
+            [[5, 4, 3], [2, 1, 0]]
+        );
+        assert_eq!(
+            indices(layout.rotate(Rotation::Deg270)),
+            [[3, 0], [4, 1], [5, 2]]
+        );
+        assert_eq!(indices(layout.mirror_x(true)), [[2, 1, 0], [5, 4, 3]]);
+        assert_eq!(indices(layout.mirror_y(true)), [[3, 4, 5], [0, 1, 2]]);
+    }
+
+    #[test]
+    fn rows_and_columns() {
+        let layout = MatrixLayout::new(3, 2).serpentine(true);
+        let mut matrix = Matrix::new(layout, vec![Led::OFF; 6]);
+        matrix.set_row(1, [Led::RED, Led::GREEN, Led::BLUE]);
+        matrix.set(0, 0, Led::WHITE);
+        assert_eq!(
+            matrix.row(1).collect::<Vec<_>>(),
+            [Led::RED, Led::GREEN, Led::BLUE]
+        );
+        assert_eq!(matrix.column(0).collect::<Vec<_>>(), [Led::WHITE, Led::RED]);
+        assert_eq!(
+            matrix.into_inner(),
+            [
+                Led::WHITE,
+                Led::OFF,
+                Led::OFF,
+                Led::BLUE,
+                Led::GREEN,
+                Led::RED
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn buffer_too_short() {
+        Matrix::new(MatrixLayout::new(4, 4), vec![Led::OFF; 15]);
+    }
+}
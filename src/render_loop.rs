@@ -0,0 +1,252 @@
+use crate::{Led, LedDriver, Result, NUM_CHANNELS};
+use std::ops::ControlFlow;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Weight of the latest frame interval in the moving average used for [`FrameInfo::fps`].
+const FPS_SMOOTHING: f64 = 0.1;
+
+/// Drives an [`LedDriver`] at a fixed frame rate.
+///
+/// Every frame, a closure is called to fill the buffers, after which they are rendered. The loop
+/// then sleeps until it is time for the next frame. If rendering falls behind, the frames that
+/// could not be rendered in time are skipped and counted as dropped.
+///
+/// # Example
+///
+/// ```
+/// # use rpi_ws281x::{Led, RenderLoop, SimulatedController};
+/// use std::ops::ControlFlow;
+///
+/// let mut controller = SimulatedController::new([10, 0]);
+/// RenderLoop::new(200.0)
+///     .run(&mut controller, |[strip, _], frame| {
+///         let hue = (frame.elapsed().as_millis() / 4) as u8;
+///         strip.iter_mut().for_each(|led| *led = Led::rainbow(hue));
+///         if frame.index() < 4 {
+///             ControlFlow::Continue(())
+///         } else {
+///             ControlFlow::Break(())
+///         }
+///     })
+///     .unwrap();
+/// assert_eq!(controller.frames().len(), 5);
+/// ```
+#[derive(Debug, Clone)]
+pub struct RenderLoop {
+    frame_duration: Duration,
+}
+
+impl RenderLoop {
+    /// Creates a new render loop that tries to render `fps` frames per second. Frame rates above
+    /// one frame per nanosecond are capped to that.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `fps` is not a positive, finite number.
+    pub fn new(fps: f64) -> Self {
+        assert!(
+            fps > 0.0 && fps.is_finite(),
+            "fps must be positive and finite"
+        );
+        Self {
+            frame_duration: Duration::from_secs_f64(1.0 / fps).max(Duration::from_nanos(1)),
+        }
+    }
+
+    /// Returns the time between frames. This is the duration given by the target frame rate,
+    /// or the minimum time between frames required by the driver, whichever is longer.
+    pub fn frame_duration(&self, driver: &impl LedDriver) -> Duration {
+        self.frame_duration.max(driver.min_frame_interval())
+    }
+
+    /// Runs the loop until `frame` returns `ControlFlow::Break` or rendering fails.
+    ///
+    /// `frame` is called once per frame with the buffers of all channels and information about
+    /// the current frame. Whatever it leaves in the buffers is then rendered.
+    pub fn run<D, F>(&self, driver: &mut D, mut frame: F) -> Result<()>
+    where
+        D: LedDriver + ?Sized,
+        F: FnMut([&mut [Led]; NUM_CHANNELS], &FrameInfo) -> ControlFlow<()>,
+    {
+        let frame_duration = self.frame_duration.max(driver.min_frame_interval());
+        let start = Instant::now();
+        let mut info = FrameInfo {
+            index: 0,
+            elapsed: Duration::from_secs(0),
+            delta: Duration::from_secs(0),
+            dropped_frames: 0,
+            fps: 1.0 / frame_duration.as_secs_f64(),
+        };
+        let mut next_frame = start;
+        loop {
+            let flow = frame(driver.buffers(), &info);
+            driver.render()?;
+            if let ControlFlow::Break(()) = flow {
+                return Ok(());
+            }
+
+            next_frame += frame_duration;
+            let now = Instant::now();
+            match next_frame.checked_duration_since(now) {
+                Some(sleep) => thread::sleep(sleep),
+                None => {
+                    // Skip all frames whose time has already passed.
+                    let behind = now.duration_since(next_frame);
+                    let skipped = (behind.as_nanos() / frame_duration.as_nanos()) as u32 + 1;
+                    next_frame += frame_duration * skipped;
+                    info.dropped_frames += u64::from(skipped);
+                    thread::sleep(next_frame.saturating_duration_since(now));
+                }
+            }
+
+            let elapsed = start.elapsed();
+            info.index += 1;
+            info.delta = elapsed - info.elapsed;
+            info.elapsed = elapsed;
+            if info.delta > Duration::from_secs(0) {
+                let fps = 1.0 / info.delta.as_secs_f64();
+                info.fps += (fps - info.fps) * FPS_SMOOTHING;
+            }
+        }
+    }
+}
+
+/// Information about the current frame of a [`RenderLoop`].
+#[derive(Debug, Clone)]
+pub struct FrameInfo {
+    index: u64,
+    elapsed: Duration,
+    delta: Duration,
+    dropped_frames: u64,
+    fps: f64,
+}
+
+impl FrameInfo {
+    /// Returns the number of frames rendered before this one.
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    /// Returns the time since the loop started.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Returns the time since the previous frame. Zero for the first frame.
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    /// Returns the total number of frames skipped so far because rendering was too slow.
+    pub fn dropped_frames(&self) -> u64 {
+        self.dropped_frames
+    }
+
+    /// Returns the actual frame rate, as a moving average over the last few frames.
+    pub fn fps(&self) -> f64 {
+        self.fps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RenderLoop;
+    use crate::{Channel, Controller, Led, SimulatedController, StripType};
+    use std::ops::ControlFlow;
+    use std::time::Duration;
+
+    #[test]
+    fn renders_at_frame_rate() {
+        let mut controller = SimulatedController::new([1, 1]);
+        let mut deltas = Vec::new();
+        RenderLoop::new(500.0)
+            .run(&mut controller, |[a, b], frame| {
+                a[0] = Led::new(0, frame.index() as u8, 0, 0);
+                b[0] = Led::BLUE;
+                deltas.push(frame.delta());
+                if frame.index() == 9 {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            })
+            .unwrap();
+
+        let frames = controller.frames();
+        assert_eq!(frames.len(), 10);
+        assert_eq!(frames[3].buffer(0), &[Led::new(0, 3, 0, 0)]);
+        assert_eq!(frames[3].buffer(1), &[Led::BLUE]);
+        assert_eq!(deltas[0], Duration::from_secs(0));
+        // Frames are scheduled on a fixed grid, so a late frame can be followed by a shorter
+        // interval. Over the whole run the frame rate still holds.
+        let span = frames[9].timestamp() - frames[0].timestamp();
+        assert!(span >= Duration::from_millis(15), "{:?}", span);
+    }
+
+    #[test]
+    fn huge_frame_rate() {
+        let mut controller = SimulatedController::new([1, 0]);
+        RenderLoop::new(1e12)
+            .run(&mut controller, |_, frame| {
+                if frame.index() == 2 {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            })
+            .unwrap();
+        assert_eq!(controller.frames().len(), 3);
+    }
+
+    #[test]
+    #[should_panic]
+    fn infinite_frame_rate() {
+        RenderLoop::new(f64::INFINITY);
+    }
+
+    #[test]
+    fn counts_dropped_frames() {
+        let mut controller = SimulatedController::new([1, 0]);
+        let mut dropped = 0;
+        RenderLoop::new(1000.0)
+            .run(&mut controller, |_, frame| {
+                dropped = frame.dropped_frames();
+                if frame.index() == 0 {
+                    std::thread::sleep(Duration::from_millis(10));
+                }
+                if frame.index() == 2 {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            })
+            .unwrap();
+        assert!(dropped >= 8, "{}", dropped);
+    }
+
+    #[test]
+    fn respects_min_frame_interval() {
+        let controller = Controller::builder(10)
+            .channel(
+                Channel::builder(18, 1000)
+                    .strip_type(StripType::Grbw)
+                    .build(),
+            )
+            .build_simulated();
+        // 1000 LEDs * 32 bits / 800 kHz + 55 us reset
+        assert_eq!(
+            controller.min_frame_interval(),
+            Duration::from_micros(40_055)
+        );
+        let render_loop = RenderLoop::new(100.0);
+        assert_eq!(
+            render_loop.frame_duration(&controller),
+            Duration::from_micros(40_055)
+        );
+        assert_eq!(
+            RenderLoop::new(10.0).frame_duration(&controller),
+            Duration::from_millis(100)
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use crate::color::kelvin_to_rgb;
+use crate::Led;
+
+/// Strategies for moving the white part of an RGB color over to the white channel of an RGBW
+/// LED. See [`Led::rgb_to_rgbw`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum WhiteExtraction {
+    /// Moves the smallest of the red, green and blue values to the white channel. Correct for
+    /// white LEDs that have the same tint as the RGB LEDs at full brightness.
+    Min,
+    /// Treats the white LED as having the given color temperature in Kelvin, and moves as much
+    /// of that tint of white as fits within the RGB color over to the white channel. Typical
+    /// SK6812 RGBW strips come with warm white (~3000 K), neutral white (~4500 K) or cool white
+    /// (~6500 K) LEDs.
+    ColorTemperature(u16),
+}
+
+impl Led {
+    /// Moves the white part of the RGB color in this [`Led`] over to the white channel,
+    /// according to the given strategy. Any value already in the white channel is kept and
+    /// added to.
+    ///
+    /// This makes RGB content look right on RGBW strips, with better color rendering and
+    /// lower power draw than mixing white from the RGB LEDs.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use rpi_ws281x::{Led, WhiteExtraction};
+    /// let pink = Led::new(0, 255, 100, 150);
+    /// assert_eq!(pink.rgb_to_rgbw(WhiteExtraction::Min), Led::new(100, 155, 0, 50));
+    /// ```
+    pub fn rgb_to_rgbw(self, strategy: WhiteExtraction) -> Self {
+        let (r, g, b) = (self.red(), self.green(), self.blue());
+        match strategy {
+            WhiteExtraction::Min => {
+                let white = r.min(g).min(b);
+                Self::new(
+                    self.white().saturating_add(white),
+                    r - white,
+                    g - white,
+                    b - white,
+                )
+            }
+            WhiteExtraction::ColorTemperature(kelvin) => {
+                let (wr, wg, wb) = kelvin_to_rgb(f32::from(kelvin));
+                // How much of the white LED's tint fits inside each RGB component.
+                let fit = |value: u8, tint: f32| {
+                    if tint > 0.0 {
+                        f32::from(value) / tint
+                    } else {
+                        f32::INFINITY
+                    }
+                };
+                let white = fit(r, wr).min(fit(g, wg)).min(fit(b, wb)).min(255.0);
+                let remove =
+                    |value: u8, tint: f32| (f32::from(value) - white * tint).round().max(0.0) as u8;
+                Self::new(
+                    self.white().saturating_add(white.round() as u8),
+                    remove(r, wr),
+                    remove(g, wg),
+                    remove(b, wb),
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WhiteExtraction;
+    use crate::Led;
+
+    #[test]
+    fn min() {
+        let extract = |led: Led| led.rgb_to_rgbw(WhiteExtraction::Min);
+        assert_eq!(extract(Led::RGB_WHITE), Led::WHITE);
+        assert_eq!(extract(Led::RED), Led::RED);
+        assert_eq!(extract(Led::new(0, 10, 20, 30)), Led::new(10, 0, 10, 20));
+        assert_eq!(extract(Led::new(250, 10, 20, 30)), Led::new(255, 0, 10, 20));
+        // Extracting twice changes nothing more.
+        let once = extract(Led::new(3, 40, 50, 60));
+        assert_eq!(extract(once), once);
+    }
+
+    #[test]
+    fn color_temperature() {
+        let extract = |led: Led, kelvin| led.rgb_to_rgbw(WhiteExtraction::ColorTemperature(kelvin));
+        // Pure colors contain no white of any temperature.
+        assert_eq!(extract(Led::BLUE, 3000), Led::BLUE);
+        assert_eq!(extract(Led::GREEN, 6500), Led::GREEN);
+        // Around 6600 K the approximation is pure white, so this behaves like `Min`.
+        assert_eq!(
+            extract(Led::new(0, 10, 20, 30), 6600),
+            Led::new(10, 0, 10, 20)
+        );
+        // A warm white LED fully replaces an RGB mix with the same tint.
+        let (r, g, b) = crate::color::kelvin_to_rgb(3000.0);
+        let to_u8 = |v: f32| (v * 255.0).round() as u8;
+        let warm = Led::new(0, to_u8(r), to_u8(g), to_u8(b));
+        let extracted = extract(warm, 3000);
+        assert_eq!(extracted.white(), 255);
+        assert!(extracted.red() <= 1 && extracted.green() <= 1 && extracted.blue() <= 1);
+    }
+}
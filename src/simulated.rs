@@ -0,0 +1,364 @@
+use crate::strip::{self, Strip};
+use crate::{Led, LedDriver, Result, WhiteExtraction, NUM_CHANNELS};
+use std::time::{Duration, Instant};
+
+/// An in-memory stand-in for [`Controller`](crate::Controller) that never touches any hardware.
+///
+/// It behaves like a real controller, but instead of sending the LED values to a strip, every
+/// rendered frame is stored in memory together with the time it was rendered. This makes it
+/// possible to test code that drives LEDs on machines without a Raspberry Pi.
+///
+/// # Example
+///
+/// ```
+/// # use rpi_ws281x::{Channel, Controller, Led, LedDriver};
+/// let mut controller = Controller::builder(10)
+///     .channel(Channel::builder(18, 3).build())
+///     .build_simulated();
+///
+/// controller.buffer(0)[1] = Led::RED;
+/// controller.render().unwrap();
+///
+/// let frame = controller.last_frame().unwrap();
+/// assert_eq!(frame.buffer(0), &[Led::OFF, Led::RED, Led::OFF]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SimulatedController {
+    buffers: [Vec<Led>; NUM_CHANNELS],
+    brightness: [u8; NUM_CHANNELS],
+    pub(crate) white_extraction: [Option<WhiteExtraction>; NUM_CHANNELS],
+    /// The chain of strips on each channel. White extraction is only applied to the strips with
+    /// a white channel.
+    pub(crate) strips: [Vec<Strip>; NUM_CHANNELS],
+    pub(crate) min_frame_interval: Duration,
+    frames: Vec<RenderedFrame>,
+}
+
+impl SimulatedController {
+    /// Creates a new simulated controller with the given number of LEDs on each channel.
+    /// A channel with zero LEDs behaves like a disabled channel. All channels start out at
+    /// full brightness.
+    pub fn new(channel_lens: [usize; NUM_CHANNELS]) -> Self {
+        Self {
+            buffers: [
+                vec![Led::OFF; channel_lens[0]],
+                vec![Led::OFF; channel_lens[1]],
+            ],
+            brightness: [255; NUM_CHANNELS],
+            white_extraction: [None; NUM_CHANNELS],
+            strips: Default::default(),
+            min_frame_interval: Duration::from_secs(0),
+            frames: Vec::new(),
+        }
+    }
+
+    /// Returns a mutable slice where all the LED values can be set directly.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel_index >= NUM_CHANNELS`.
+    pub fn buffer(&mut self, channel_index: usize) -> &mut [Led] {
+        &mut self.buffers[channel_index]
+    }
+
+    /// Returns mutable slices where the LED values of all channels can be set directly.
+    pub fn buffers(&mut self) -> [&mut [Led]; NUM_CHANNELS] {
+        let [channel0, channel1] = &mut self.buffers;
+        [channel0, channel1]
+    }
+
+    /// Returns the number of LEDs on the channel with the given index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel_index >= NUM_CHANNELS`.
+    pub fn channel_len(&self, channel_index: usize) -> usize {
+        self.buffers[channel_index].len()
+    }
+
+    /// Returns the time it would take a real [`Controller`](crate::Controller) with the same
+    /// configuration to send one frame. Zero unless created with
+    /// [`ControllerBuilder::build_simulated`](crate::ControllerBuilder::build_simulated).
+    pub fn min_frame_interval(&self) -> Duration {
+        self.min_frame_interval
+    }
+
+    /// Returns the brightness of the channel with the given index, between 0 and 255.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel_index >= NUM_CHANNELS`.
+    pub fn brightness(&self, channel_index: usize) -> u8 {
+        self.brightness[channel_index]
+    }
+
+    /// Sets the brightness of the channel with the given index, between 0 and 255.
+    /// It is recorded in every frame rendered after this call.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel_index >= NUM_CHANNELS`.
+    pub fn set_brightness(&mut self, channel_index: usize, brightness: u8) {
+        self.brightness[channel_index] = brightness;
+    }
+
+    /// Stores a copy of what is currently in the buffers as a new frame.
+    ///
+    /// Like [`Controller`](crate::Controller), any white extraction configured on a channel is
+    /// applied to the stored frame, but not to the buffers. On a channel with a chain of strips,
+    /// it is only applied to the strips with a white channel.
+    pub fn render(&mut self) -> Result<()> {
+        let buffers = [
+            self.process(0, &self.buffers[0]),
+            self.process(1, &self.buffers[1]),
+        ];
+        self.frames.push(RenderedFrame {
+            timestamp: Instant::now(),
+            buffers,
+            brightness: self.brightness,
+        });
+        Ok(())
+    }
+
+    /// Stores a copy of the given buffers, processed like in [`SimulatedController::render`],
+    /// as a new frame. The buffers held by this instance are left untouched.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the `&[Led]` slices are not the same length as the corresponding
+    /// channel.
+    pub fn render_buffer(&mut self, buffers: [&[Led]; NUM_CHANNELS]) -> Result<()> {
+        assert_eq!(self.buffers[0].len(), buffers[0].len());
+        assert_eq!(self.buffers[1].len(), buffers[1].len());
+
+        self.frames.push(RenderedFrame {
+            timestamp: Instant::now(),
+            buffers: [self.process(0, buffers[0]), self.process(1, buffers[1])],
+            brightness: self.brightness,
+        });
+        Ok(())
+    }
+
+    fn process(&self, channel_index: usize, leds: &[Led]) -> Vec<Led> {
+        let mut out = leds.to_vec();
+        let extraction = match self.white_extraction[channel_index] {
+            Some(extraction) => extraction,
+            None => return out,
+        };
+        let strips = &self.strips[channel_index];
+        let extract = |leds: &mut [Led]| {
+            for led in leds {
+                *led = led.rgb_to_rgbw(extraction);
+            }
+        };
+        if strips.is_empty() {
+            extract(&mut out);
+        }
+        for (strip_index, strip) in strips.iter().enumerate() {
+            if strip.strip_type().has_white() {
+                let range = strip::strip_range(strips, out.len(), strip_index);
+                extract(&mut out[range]);
+            }
+        }
+        out
+    }
+
+    /// Returns all frames rendered so far, oldest first.
+    pub fn frames(&self) -> &[RenderedFrame] {
+        &self.frames
+    }
+
+    /// Returns the most recently rendered frame, if any.
+    pub fn last_frame(&self) -> Option<&RenderedFrame> {
+        self.frames.last()
+    }
+
+    /// Removes and returns all frames rendered so far.
+    pub fn take_frames(&mut self) -> Vec<RenderedFrame> {
+        std::mem::take(&mut self.frames)
+    }
+}
+
+impl LedDriver for SimulatedController {
+    fn channel_len(&self, channel_index: usize) -> usize {
+        SimulatedController::channel_len(self, channel_index)
+    }
+
+    fn buffer(&mut self, channel_index: usize) -> &mut [Led] {
+        SimulatedController::buffer(self, channel_index)
+    }
+
+    fn buffers(&mut self) -> [&mut [Led]; NUM_CHANNELS] {
+        SimulatedController::buffers(self)
+    }
+
+    fn min_frame_interval(&self) -> Duration {
+        SimulatedController::min_frame_interval(self)
+    }
+
+    fn brightness(&self, channel_index: usize) -> u8 {
+        SimulatedController::brightness(self, channel_index)
+    }
+
+    fn set_brightness(&mut self, channel_index: usize, brightness: u8) {
+        SimulatedController::set_brightness(self, channel_index, brightness)
+    }
+
+    fn render(&mut self) -> Result<()> {
+        SimulatedController::render(self)
+    }
+
+    fn render_buffer(&mut self, buffers: [&[Led]; NUM_CHANNELS]) -> Result<()> {
+        SimulatedController::render_buffer(self, buffers)
+    }
+}
+
+/// A snapshot of the LED values of all channels, taken when a [`SimulatedController`] rendered.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RenderedFrame {
+    timestamp: Instant,
+    buffers: [Vec<Led>; NUM_CHANNELS],
+    brightness: [u8; NUM_CHANNELS],
+}
+
+impl RenderedFrame {
+    /// Returns the point in time when this frame was rendered.
+    pub fn timestamp(&self) -> Instant {
+        self.timestamp
+    }
+
+    /// Returns the LED values that were rendered to the given channel.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel_index >= NUM_CHANNELS`.
+    pub fn buffer(&self, channel_index: usize) -> &[Led] {
+        &self.buffers[channel_index]
+    }
+
+    /// Returns the brightness the given channel had when this frame was rendered.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel_index >= NUM_CHANNELS`.
+    pub fn brightness(&self, channel_index: usize) -> u8 {
+        self.brightness[channel_index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SimulatedController;
+    use crate::{Channel, Controller, Led, LedDriver, Strip, StripType, WhiteExtraction};
+
+    #[test]
+    fn render_records_frames() {
+        let mut controller = SimulatedController::new([2, 0]);
+        assert!(controller.last_frame().is_none());
+
+        controller.buffer(0)[0] = Led::RED;
+        controller.render().unwrap();
+        controller.buffer(0)[1] = Led::BLUE;
+        controller.render().unwrap();
+
+        let frames = controller.frames();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].buffer(0), &[Led::RED, Led::OFF]);
+        assert_eq!(frames[1].buffer(0), &[Led::RED, Led::BLUE]);
+        assert!(frames[0].buffer(1).is_empty());
+        assert!(frames[0].timestamp() <= frames[1].timestamp());
+    }
+
+    #[test]
+    fn render_buffer_leaves_buffers_untouched() {
+        let mut controller = SimulatedController::new([1, 2]);
+        controller
+            .render_buffer([&[Led::GREEN], &[Led::ON, Led::WHITE]])
+            .unwrap();
+
+        assert_eq!(controller.buffer(0), &[Led::OFF]);
+        let frame = controller.last_frame().unwrap();
+        assert_eq!(frame.buffer(0), &[Led::GREEN]);
+        assert_eq!(frame.buffer(1), &[Led::ON, Led::WHITE]);
+    }
+
+    #[test]
+    fn brightness_recorded_per_frame() {
+        let mut controller = SimulatedController::new([1, 1]);
+        controller.render().unwrap();
+        controller.set_brightness(1, 40);
+        controller.render().unwrap();
+
+        assert_eq!(controller.brightness(1), 40);
+        let frames = controller.frames();
+        assert_eq!(frames[0].brightness(1), 255);
+        assert_eq!(frames[1].brightness(0), 255);
+        assert_eq!(frames[1].brightness(1), 40);
+    }
+
+    #[test]
+    fn white_extraction_applied_to_frames() {
+        let mut controller = Controller::builder(10)
+            .channel(
+                Channel::builder(18, 1)
+                    .strip_type(StripType::Grbw)
+                    .white_extraction(WhiteExtraction::Min)
+                    .build(),
+            )
+            .build_simulated();
+        controller.buffer(0)[0] = Led::new(0, 10, 20, 30);
+        controller.render().unwrap();
+
+        assert_eq!(controller.buffer(0), &[Led::new(0, 10, 20, 30)]);
+        let frame = controller.last_frame().unwrap();
+        assert_eq!(frame.buffer(0), &[Led::new(10, 0, 10, 20)]);
+    }
+
+    #[test]
+    fn white_extraction_only_on_strips_with_white() {
+        let mut controller = Controller::builder(10)
+            .channel(
+                Channel::builder(18, 0)
+                    .strips([
+                        Strip::new(1, StripType::Grb),
+                        Strip::new(1, StripType::Grbw),
+                    ])
+                    .white_extraction(WhiteExtraction::Min)
+                    .build(),
+            )
+            .build_simulated();
+        controller.buffer(0).fill(Led::new(0, 10, 20, 30));
+        controller.render().unwrap();
+
+        let frame = controller.last_frame().unwrap();
+        assert_eq!(
+            frame.buffer(0),
+            &[Led::new(0, 10, 20, 30), Led::new(10, 0, 10, 20)]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn render_buffer_length_mismatch() {
+        let mut controller = SimulatedController::new([1, 0]);
+        let _ = controller.render_buffer([&[Led::OFF, Led::OFF], &[]]);
+    }
+
+    #[test]
+    fn usable_as_led_driver() {
+        fn fill(driver: &mut impl LedDriver, led: Led) {
+            for channel_index in 0..driver.channel_count() {
+                for l in driver.buffer(channel_index) {
+                    *l = led;
+                }
+            }
+            driver.render().unwrap();
+        }
+
+        let mut controller = SimulatedController::new([1, 1]);
+        fill(&mut controller, Led::ON);
+        assert_eq!(controller.take_frames().len(), 1);
+        assert!(controller.frames().is_empty());
+        assert_eq!(controller.channel_len(1), 1);
+    }
+}
@@ -0,0 +1,137 @@
+use crate::{Error, Led, LedDriver, NUM_CHANNELS};
+use smart_leds_trait::{SmartLedsWrite, White, RGB8, RGBW};
+
+impl From<RGB8> for Led {
+    /// Converts to an [`Led`] with the white channel off.
+    fn from(color: RGB8) -> Self {
+        Led::new(0, color.r, color.g, color.b)
+    }
+}
+
+impl From<RGBW<u8>> for Led {
+    fn from(color: RGBW<u8>) -> Self {
+        let White(white) = color.a;
+        Led::new(white, color.r, color.g, color.b)
+    }
+}
+
+/// Drives one channel of a [`LedDriver`] through the [`SmartLedsWrite`] trait from the
+/// `smart-leds` ecosystem, so animations written for it can run unchanged.
+///
+/// Each call to `write` renders a frame with [`LedDriver::render_buffer`]. The written channel
+/// gets the LEDs from the iterator, and the other channel shows what is currently in its buffer
+/// on the driver. The buffers held by the driver are left untouched.
+///
+/// # Example
+///
+/// ```
+/// # use rpi_ws281x::{Channel, Controller, Led, SmartLedsChannel};
+/// use smart_leds_trait::{SmartLedsWrite, RGB8};
+///
+/// let mut controller = Controller::builder(10)
+///     .channel(Channel::builder(18, 3).build())
+///     .build_simulated();
+/// let mut strip = SmartLedsChannel::new(&mut controller, 0);
+/// strip.write([RGB8::new(255, 0, 0); 3]).unwrap();
+///
+/// let frame = controller.last_frame().unwrap();
+/// assert_eq!(frame.buffer(0), &[Led::RED; 3]);
+/// ```
+#[derive(Debug)]
+pub struct SmartLedsChannel<'a, D: ?Sized> {
+    driver: &'a mut D,
+    channel_index: usize,
+    buffers: [Vec<Led>; NUM_CHANNELS],
+}
+
+impl<'a, D: LedDriver + ?Sized> SmartLedsChannel<'a, D> {
+    /// Creates an adapter writing to the channel with the given index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel_index >= driver.channel_count()`.
+    pub fn new(driver: &'a mut D, channel_index: usize) -> Self {
+        assert!(
+            channel_index < driver.channel_count(),
+            "channel index out of bounds"
+        );
+        Self {
+            driver,
+            channel_index,
+            buffers: Default::default(),
+        }
+    }
+
+    /// Returns the index of the channel this adapter writes to.
+    pub fn channel_index(&self) -> usize {
+        self.channel_index
+    }
+}
+
+impl<D: LedDriver + ?Sized> SmartLedsWrite for SmartLedsChannel<'_, D> {
+    type Error = Error;
+    type Color = Led;
+
+    /// Renders the given LEDs on the channel. LEDs past the end of the channel are ignored, and
+    /// if there are fewer than the channel has, the rest are turned off.
+    fn write<T, I>(&mut self, iterator: T) -> Result<(), Self::Error>
+    where
+        T: IntoIterator<Item = I>,
+        I: Into<Self::Color>,
+    {
+        for (channel_index, buffer) in self.buffers.iter_mut().enumerate() {
+            buffer.clear();
+            if channel_index != self.channel_index {
+                buffer.extend_from_slice(self.driver.buffer(channel_index));
+            }
+        }
+        let len = self.driver.channel_len(self.channel_index);
+        let buffer = &mut self.buffers[self.channel_index];
+        buffer.extend(iterator.into_iter().take(len).map(Into::into));
+        buffer.resize(len, Led::OFF);
+        let [channel0, channel1] = &self.buffers;
+        self.driver.render_buffer([channel0, channel1])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SmartLedsChannel;
+    use crate::{Channel, Controller, Led};
+    use smart_leds_trait::{SmartLedsWrite, White, RGB8, RGBW};
+
+    #[test]
+    fn conversions() {
+        assert_eq!(Led::from(RGB8::new(1, 2, 3)), Led::new(0, 1, 2, 3));
+        let rgbw = RGBW {
+            r: 1,
+            g: 2,
+            b: 3,
+            a: White(4),
+        };
+        assert_eq!(Led::from(rgbw), Led::new(4, 1, 2, 3));
+    }
+
+    #[test]
+    fn writes_one_channel() {
+        let mut controller = Controller::builder(10)
+            .channels([
+                Channel::builder(18, 3).build(),
+                Channel::builder(13, 2).build(),
+            ])
+            .build_simulated();
+        controller.buffer(0)[0] = Led::GREEN;
+        controller.buffer(1)[0] = Led::BLUE;
+
+        let mut strip = SmartLedsChannel::new(&mut controller, 1);
+        strip
+            .write([RGB8::new(1, 0, 0), RGB8::new(2, 0, 0), RGB8::new(3, 0, 0)])
+            .unwrap();
+        strip.write([RGB8::new(4, 0, 0)]).unwrap();
+
+        let frame = controller.last_frame().unwrap();
+        assert_eq!(frame.buffer(0), &[Led::GREEN, Led::OFF, Led::OFF]);
+        assert_eq!(frame.buffer(1), &[Led::new(0, 4, 0, 0), Led::OFF]);
+        assert_eq!(controller.buffer(1), &[Led::BLUE, Led::OFF]);
+    }
+}
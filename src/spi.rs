@@ -0,0 +1,346 @@
+use crate::encoder::SYMBOL_BITS;
+use crate::{
+    strip, sys, ChannelSettings, Encoder, Error, Led, LedDriver, Result, Strip, WhiteExtraction,
+    LED_RESET_TIME, NUM_CHANNELS,
+};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::os::raw::{c_int, c_ulong};
+use std::os::unix::io::AsRawFd;
+use std::time::Duration;
+
+/// The SPI device connected to GPIO 10.
+const SPI_DEVICE: &str = "/dev/spidev0.0";
+
+/// Linux spidev ioctl requests, `_IOW('k', nr, size)`.
+const SPI_IOC_WR_MODE: c_ulong = 0x4001_6b01;
+const SPI_IOC_WR_BITS_PER_WORD: c_ulong = 0x4001_6b03;
+const SPI_IOC_WR_MAX_SPEED_HZ: c_ulong = 0x4004_6b04;
+
+/// Drives a single LED strip connected to GPIO 10 through the Linux SPI driver, without any of
+/// the DMA and PWM machinery of the C library. Does not need root, only access to
+/// `/dev/spidev0.0`.
+///
+/// Created with [`ControllerBuilder::build_spi`](crate::ControllerBuilder::build_spi). Only
+/// channel 0 is used, channel 1 always has zero LEDs.
+///
+/// Long strips might need a larger SPI buffer than the kernel default of 4096 bytes. It can be
+/// raised with `spidev.bufsiz=65536` in `/boot/cmdline.txt`.
+pub struct SpiController {
+    buffers: [Vec<Led>; NUM_CHANNELS],
+    output: Output,
+}
+
+/// Everything needed to turn LED values into bytes on the SPI device.
+struct Output {
+    /// The configured SPI device. Anything else that captures the bytes in tests.
+    device: Box<dyn Write + Send>,
+    encoder: Encoder,
+    white_extraction: Option<WhiteExtraction>,
+    /// The chain of strips on the channel, if any. See [`ChannelSettings::strips`].
+    strips: Vec<Strip>,
+    /// Holds the LED values after white extraction or repacking for strips.
+    scratch: Vec<Led>,
+    /// The encoded bytes of the last frame.
+    bytes: Vec<u8>,
+}
+
+impl SpiController {
+    /// Opens and configures the SPI device for the given channel. The configuration must
+    /// already be validated.
+    pub(crate) fn open(
+        freq: u32,
+        channel: &sys::ws2811_channel_t,
+        settings: ChannelSettings,
+    ) -> Result<Self> {
+        let device = OpenOptions::new()
+            .write(true)
+            .open(SPI_DEVICE)
+            .map_err(Error::SpiDevice)?;
+        let mode: u8 = 0;
+        let bits_per_word: u8 = 8;
+        let speed: u32 = freq * SYMBOL_BITS;
+        unsafe {
+            ioctl(&device, SPI_IOC_WR_MODE, &mode)?;
+            ioctl(&device, SPI_IOC_WR_BITS_PER_WORD, &bits_per_word)?;
+            ioctl(&device, SPI_IOC_WR_MAX_SPEED_HZ, &speed)?;
+        }
+        Ok(Self::new(freq, channel, settings, Box::new(device)))
+    }
+
+    /// Creates a controller writing the encoded frames to `device`, which must already be
+    /// configured.
+    fn new(
+        freq: u32,
+        channel: &sys::ws2811_channel_t,
+        settings: ChannelSettings,
+        device: Box<dyn Write + Send>,
+    ) -> Self {
+        let led_count = settings.channel_len();
+        Self {
+            buffers: [vec![Led::OFF; led_count], Vec::new()],
+            output: Output {
+                device,
+                encoder: Encoder::from_raw(channel, freq, settings.gamma.as_deref()),
+                white_extraction: settings.white_extraction,
+                strips: settings.strips,
+                scratch: Vec::new(),
+                bytes: Vec::new(),
+            },
+        }
+    }
+
+    /// Returns a mutable slice where all the LED values can be set directly. The values are
+    /// sent to the LEDs on the next call to [`SpiController::render`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel_index >= NUM_CHANNELS`.
+    pub fn buffer(&mut self, channel_index: usize) -> &mut [Led] {
+        &mut self.buffers[channel_index]
+    }
+
+    /// Returns mutable slices where the LED values of all channels can be set directly.
+    pub fn buffers(&mut self) -> [&mut [Led]; NUM_CHANNELS] {
+        let [channel0, channel1] = &mut self.buffers;
+        [channel0, channel1]
+    }
+
+    /// Returns the number of LEDs on the channel with the given index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel_index >= NUM_CHANNELS`.
+    pub fn channel_len(&self, channel_index: usize) -> usize {
+        self.buffers[channel_index].len()
+    }
+
+    /// Returns the time it takes to send one frame to the LEDs.
+    pub fn min_frame_interval(&self) -> Duration {
+        let encoder = &self.output.encoder;
+        let packed_len = if self.output.strips.is_empty() {
+            self.buffers[0].len() as u64
+        } else {
+            strip::packed_len(&self.output.strips)
+        };
+        let bits = packed_len * encoder.bits_per_led() as u64;
+        Duration::from_nanos(bits * 1_000_000_000 / u64::from(encoder.freq())) + LED_RESET_TIME
+    }
+
+    /// Returns the brightness of the channel with the given index, between 0 and 255.
+    /// Channel 1 is never used and always returns 0.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel_index >= NUM_CHANNELS`.
+    pub fn brightness(&self, channel_index: usize) -> u8 {
+        assert!(channel_index < NUM_CHANNELS);
+        if channel_index == 0 {
+            self.output.encoder.brightness()
+        } else {
+            0
+        }
+    }
+
+    /// Sets the brightness of channel 0, between 0 and 255. Takes effect on the next render.
+    /// Setting the brightness of channel 1 has no effect.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel_index >= NUM_CHANNELS`.
+    pub fn set_brightness(&mut self, channel_index: usize, brightness: u8) {
+        assert!(channel_index < NUM_CHANNELS);
+        if channel_index == 0 {
+            self.output.encoder.set_brightness(brightness);
+        }
+    }
+
+    /// Sends what is currently in the buffer to the LEDs. Blocks until the transfer is done.
+    pub fn render(&mut self) -> Result<()> {
+        self.output.send(&self.buffers[0])
+    }
+
+    /// Sends the given buffers to the LEDs, leaving the buffers held by this instance
+    /// untouched. Blocks until the transfer is done.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the `&[Led]` slices are not the same length as the corresponding channel.
+    pub fn render_buffer(&mut self, buffers: [&[Led]; NUM_CHANNELS]) -> Result<()> {
+        assert_eq!(self.buffers[0].len(), buffers[0].len());
+        assert_eq!(self.buffers[1].len(), buffers[1].len());
+        self.output.send(buffers[0])
+    }
+}
+
+impl Output {
+    fn send(&mut self, leds: &[Led]) -> Result<()> {
+        let leds = if !self.strips.is_empty() {
+            strip::pack(&self.strips, leds, self.white_extraction, &mut self.scratch);
+            &self.scratch
+        } else if let Some(extraction) = self.white_extraction {
+            self.scratch.clear();
+            self.scratch
+                .extend(leds.iter().map(|led| led.rgb_to_rgbw(extraction)));
+            &self.scratch
+        } else {
+            leds
+        };
+        self.encoder.encode_spi(leds, &mut self.bytes);
+        self.device.write_all(&self.bytes).map_err(Error::SpiDevice)
+    }
+}
+
+impl LedDriver for SpiController {
+    fn channel_len(&self, channel_index: usize) -> usize {
+        SpiController::channel_len(self, channel_index)
+    }
+
+    fn buffer(&mut self, channel_index: usize) -> &mut [Led] {
+        SpiController::buffer(self, channel_index)
+    }
+
+    fn buffers(&mut self) -> [&mut [Led]; NUM_CHANNELS] {
+        SpiController::buffers(self)
+    }
+
+    fn brightness(&self, channel_index: usize) -> u8 {
+        SpiController::brightness(self, channel_index)
+    }
+
+    fn set_brightness(&mut self, channel_index: usize, brightness: u8) {
+        SpiController::set_brightness(self, channel_index, brightness)
+    }
+
+    fn render(&mut self) -> Result<()> {
+        SpiController::render(self)
+    }
+
+    fn render_buffer(&mut self, buffers: [&[Led]; NUM_CHANNELS]) -> Result<()> {
+        SpiController::render_buffer(self, buffers)
+    }
+
+    fn min_frame_interval(&self) -> Duration {
+        SpiController::min_frame_interval(self)
+    }
+}
+
+unsafe fn ioctl<T>(device: &File, request: c_ulong, value: &T) -> Result<()> {
+    extern "C" {
+        fn ioctl(fd: c_int, request: c_ulong, ...) -> c_int;
+    }
+    if ioctl(device.as_raw_fd(), request, value as *const T) < 0 {
+        Err(Error::SpiDevice(io::Error::last_os_error()))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SpiController;
+    use crate::strip::{self, CARRIER_STRIP_TYPE};
+    use crate::{sys, Channel, Encoder, Led, Strip, StripType, WhiteExtraction};
+    use std::io::{self, Write};
+    use std::sync::{Arc, Mutex};
+
+    /// Captures all bytes written to the SPI device.
+    #[derive(Clone, Default)]
+    struct Capture(Arc<Mutex<Vec<u8>>>);
+
+    impl Capture {
+        fn take(&self) -> Vec<u8> {
+            std::mem::take(&mut self.0.lock().unwrap())
+        }
+    }
+
+    impl Write for Capture {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn controller(channel: Channel) -> (SpiController, Capture) {
+        let capture = Capture::default();
+        let controller = SpiController::new(
+            sys::WS2811_TARGET_FREQ,
+            &channel.raw,
+            channel.settings,
+            Box::new(capture.clone()),
+        );
+        (controller, capture)
+    }
+
+    fn encode_spi(encoder: &Encoder, leds: &[Led]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        encoder.encode_spi(leds, &mut bytes);
+        bytes
+    }
+
+    #[test]
+    fn renders_encoded_frame() {
+        let channel = Channel::builder(10, 3)
+            .strip_type(StripType::Grb)
+            .brightness(128)
+            .build();
+        let (mut controller, capture) = controller(channel);
+        let leds = [Led::RED, Led::new(0, 1, 2, 3), Led::WHITE];
+        controller.buffer(0).copy_from_slice(&leds);
+        controller.render().unwrap();
+
+        let mut encoder = Encoder::new(StripType::Grb);
+        encoder.set_brightness(128);
+        let bytes = capture.take();
+        assert_eq!(bytes, encode_spi(&encoder, &leds));
+        // Three symbol bits per data bit, followed by the reset latch.
+        let data_len = 3 * 24 * 3 / 8;
+        let reset_len = (encoder.reset_bits() + 7) / 8;
+        assert_eq!(bytes.len(), data_len + reset_len);
+        assert!(bytes[data_len..].iter().all(|&byte| byte == 0));
+
+        controller.render_buffer([&[Led::BLUE; 3], &[]]).unwrap();
+        assert_eq!(capture.take(), encode_spi(&encoder, &[Led::BLUE; 3]));
+        assert_eq!(controller.buffer(0), &leds);
+    }
+
+    #[test]
+    fn applies_white_extraction() {
+        let channel = Channel::builder(10, 1)
+            .strip_type(StripType::Grbw)
+            .white_extraction(WhiteExtraction::Min)
+            .build();
+        let (mut controller, capture) = controller(channel);
+        controller.buffer(0)[0] = Led::new(0, 10, 20, 30);
+        controller.render().unwrap();
+
+        let encoder = Encoder::new(StripType::Grbw);
+        let expected = encode_spi(&encoder, &[Led::new(10, 0, 10, 20)]);
+        assert_eq!(capture.take(), expected);
+    }
+
+    #[test]
+    fn packs_strips() {
+        let strips = [
+            Strip::new(2, StripType::Grb),
+            Strip::new(1, StripType::Grbw).reverse(true),
+        ];
+        let channel = Channel::builder(10, 0)
+            .strips(strips)
+            .white_extraction(WhiteExtraction::Min)
+            .build();
+        let (mut controller, capture) = controller(channel);
+        let leds = [Led::RED, Led::GREEN, Led::new(0, 10, 20, 30)];
+        controller.buffer(0).copy_from_slice(&leds);
+        controller.render().unwrap();
+
+        let mut packed = Vec::new();
+        strip::pack(&strips, &leds, Some(WhiteExtraction::Min), &mut packed);
+        let expected = encode_spi(&Encoder::new(CARRIER_STRIP_TYPE), &packed);
+        assert_eq!(capture.take(), expected);
+    }
+}
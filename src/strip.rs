@@ -0,0 +1,203 @@
+use crate::encoder::channel_shifts;
+use crate::{Led, StripType, WhiteExtraction};
+use std::ops::Range;
+
+/// The strip type a channel is configured with in the C library when it drives a chain of
+/// [`Strip`]s. The LEDs are repacked on the Rust side into words of three bytes each, sent in
+/// order. Brightness and gamma are applied per byte, so the C library can still do those.
+pub(crate) const CARRIER_STRIP_TYPE: StripType = StripType::Rgb;
+
+/// One physical LED strip in a chain of strips connected to the same channel.
+/// See [`ChannelBuilder::strips`](crate::ChannelBuilder::strips).
+///
+/// Strips in a chain can have different strip types, and can be mounted in the reverse
+/// direction. Each strip is presented as its own slice of the channel buffer, in the order the
+/// strips are chained, with the LEDs always in logical order.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Strip {
+    led_count: u32,
+    strip_type: StripType,
+    reversed: bool,
+}
+
+impl Strip {
+    /// Creates a new strip with the given amount of LEDs of the given type.
+    pub fn new(led_count: u32, strip_type: StripType) -> Self {
+        Self {
+            led_count,
+            strip_type,
+            reversed: false,
+        }
+    }
+
+    /// Sets if the strip is mounted in reverse, so the data enters at what should be the last
+    /// LED. Defaults to `false`.
+    pub fn reverse(mut self, reversed: bool) -> Self {
+        self.reversed = reversed;
+        self
+    }
+
+    /// Returns the number of LEDs on the strip.
+    pub fn led_count(&self) -> u32 {
+        self.led_count
+    }
+
+    /// Returns the type of the LEDs on the strip.
+    pub fn strip_type(&self) -> StripType {
+        self.strip_type
+    }
+
+    /// Returns `true` if the strip is mounted in reverse.
+    pub fn is_reversed(&self) -> bool {
+        self.reversed
+    }
+
+    fn len(&self) -> usize {
+        self.led_count as usize
+    }
+
+    fn bytes_per_led(&self) -> usize {
+        if self.strip_type.has_white() {
+            4
+        } else {
+            3
+        }
+    }
+}
+
+/// Returns the total number of LEDs on all strips.
+pub(crate) fn total_len(strips: &[Strip]) -> usize {
+    strips.iter().map(Strip::len).sum()
+}
+
+/// Returns the number of LED words needed to send all strips with [`CARRIER_STRIP_TYPE`].
+/// Computed in 64 bits, since it can exceed what the C library supports, which is checked in
+/// [`ControllerBuilder::validate`](crate::ControllerBuilder::validate).
+pub(crate) fn packed_len(strips: &[Strip]) -> u64 {
+    let bytes: u64 = strips
+        .iter()
+        .map(|s| u64::from(s.led_count) * s.bytes_per_led() as u64)
+        .sum();
+    (bytes + 2) / 3
+}
+
+/// Returns the range of the channel buffer holding the strip with the given index.
+/// A channel without strips is presented as a single strip covering the whole buffer.
+///
+/// # Panics
+///
+/// Panics if there is no strip with the given index.
+pub(crate) fn strip_range(
+    strips: &[Strip],
+    channel_len: usize,
+    strip_index: usize,
+) -> Range<usize> {
+    if strips.is_empty() {
+        assert_eq!(strip_index, 0, "strip index out of bounds");
+        return 0..channel_len;
+    }
+    let start = total_len(&strips[..strip_index]);
+    start..start + strips[strip_index].len()
+}
+
+/// Repacks the LEDs of a chain of strips into LED words for a channel using
+/// [`CARRIER_STRIP_TYPE`]. White extraction is only applied to strips with a white channel.
+/// Replaces the contents of `out`.
+pub(crate) fn pack(
+    strips: &[Strip],
+    leds: &[Led],
+    white_extraction: Option<WhiteExtraction>,
+    out: &mut Vec<Led>,
+) {
+    out.clear();
+    let mut word = 0u32;
+    let mut used_bytes = 0;
+    let mut start = 0;
+    for strip in strips {
+        let strip_leds = &leds[start..start + strip.len()];
+        start += strip.len();
+        let extraction = white_extraction.filter(|_| strip.strip_type.has_white());
+        let mut push_led = |led: Led| {
+            let led = match extraction {
+                Some(extraction) => led.rgb_to_rgbw(extraction),
+                None => led,
+            };
+            let raw = u32::from(led);
+            for shift in channel_shifts(strip.strip_type as u32) {
+                word = (word << 8) | ((raw >> shift) & 0xff);
+                used_bytes += 1;
+                if used_bytes == 3 {
+                    out.push(Led::from(word));
+                    word = 0;
+                    used_bytes = 0;
+                }
+            }
+        };
+        if strip.reversed {
+            strip_leds.iter().rev().copied().for_each(&mut push_led);
+        } else {
+            strip_leds.iter().copied().for_each(&mut push_led);
+        }
+    }
+    if used_bytes > 0 {
+        out.push(Led::from(word << (8 * (3 - used_bytes))));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pack, packed_len, strip_range, total_len, Strip, CARRIER_STRIP_TYPE};
+    use crate::{Encoder, Led, StripType, WhiteExtraction};
+
+    #[test]
+    fn lengths() {
+        let strips = [
+            Strip::new(2, StripType::Grb),
+            Strip::new(3, StripType::Grbw),
+        ];
+        assert_eq!(total_len(&strips), 5);
+        // 6 + 12 bytes.
+        assert_eq!(packed_len(&strips), 6);
+        assert_eq!(packed_len(&[Strip::new(1, StripType::Rgbw)]), 2);
+        assert_eq!(strip_range(&strips, 5, 0), 0..2);
+        assert_eq!(strip_range(&strips, 5, 1), 2..5);
+        assert_eq!(strip_range(&[], 7, 0), 0..7);
+    }
+
+    #[test]
+    fn packed_wire_order() {
+        let strips = [
+            Strip::new(2, StripType::Grb).reverse(true),
+            Strip::new(1, StripType::Rgbw),
+        ];
+        let leds = [
+            Led::new(0, 1, 2, 3),
+            Led::new(0, 4, 5, 6),
+            Led::new(10, 7, 8, 9),
+        ];
+        let mut packed = Vec::new();
+        pack(&strips, &leds, None, &mut packed);
+        assert_eq!(packed.len() as u64, packed_len(&strips));
+
+        let wire: Vec<u8> = Encoder::new(CARRIER_STRIP_TYPE)
+            .color_bytes(&packed)
+            .collect();
+        // The first strip is reversed and GRB, the second one RGBW, padded to whole words.
+        assert_eq!(wire, [5, 4, 6, 2, 1, 3, 7, 8, 9, 10, 0, 0]);
+    }
+
+    #[test]
+    fn white_extraction_only_on_white_strips() {
+        let strips = [
+            Strip::new(1, StripType::Rgb),
+            Strip::new(1, StripType::Rgbw),
+        ];
+        let leds = [Led::new(0, 50, 60, 70), Led::new(0, 50, 60, 70)];
+        let mut packed = Vec::new();
+        pack(&strips, &leds, Some(WhiteExtraction::Min), &mut packed);
+        let wire: Vec<u8> = Encoder::new(CARRIER_STRIP_TYPE)
+            .color_bytes(&packed)
+            .collect();
+        assert_eq!(wire, [50, 60, 70, 0, 10, 20, 50, 0, 0]);
+    }
+}
@@ -23,9 +23,40 @@ pub enum StripType {
 }
 
 impl StripType {
+    /// Returns `true` if this type of LED has a separate white channel, like the SK6812 RGBW
+    /// variants.
+    pub fn has_white(self) -> bool {
+        matches!(
+            self,
+            Self::Rgbw | Self::Rbgw | Self::Grbw | Self::Gbrw | Self::Brgw | Self::Bgrw
+        )
+    }
+
     pub(crate) fn as_raw(self) -> i32 {
         i32::try_from(self as u32).unwrap()
     }
+
+    /// Converts back from the value the C library uses. Returns `None` for values not matching
+    /// any strip type, like the zero of a disabled channel.
+    pub(crate) fn from_raw(raw: u32) -> Option<Self> {
+        [
+            Self::Rgb,
+            Self::Rbg,
+            Self::Grb,
+            Self::Gbr,
+            Self::Brg,
+            Self::Bgr,
+            Self::Rgbw,
+            Self::Rbgw,
+            Self::Grbw,
+            Self::Gbrw,
+            Self::Brgw,
+            Self::Bgrw,
+        ]
+        .iter()
+        .copied()
+        .find(|&strip_type| strip_type as u32 == raw)
+    }
 }
 
 impl std::str::FromStr for StripType {
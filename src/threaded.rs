@@ -0,0 +1,459 @@
+use crate::driver_thread::{self, DriverInfo};
+use crate::{Error, Led, LedDriver, Result, NUM_CHANNELS};
+use std::cell::UnsafeCell;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Set in `Shared::middle` when the slot in the middle holds a frame not yet rendered.
+const FRESH: usize = 0b100;
+const INDEX_MASK: usize = 0b011;
+
+/// Runs an [`LedDriver`] on a dedicated background thread.
+///
+/// Frames are handed over to the render thread through a lock-free triple buffer. Submitting a
+/// frame never blocks on rendering, and the render thread always renders the most recently
+/// submitted frame. If frames are submitted faster than they can be rendered, the ones in
+/// between are skipped. [`ThreadedController::wait`] blocks until the most recently submitted
+/// frame has been rendered and fully sent out.
+///
+/// The driver is created on the render thread and never leaves it, so it does not have to be
+/// [`Send`]. This allows using a [`Controller`](crate::Controller), which is bound to the thread
+/// it was created on, while the `ThreadedController` itself can be moved to any thread.
+///
+/// # Example
+///
+/// ```no_run
+/// # use rpi_ws281x::{Channel, Controller, Led, ThreadedController};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut controller = ThreadedController::spawn(|| {
+///     Controller::builder(10)
+///         .channel(Channel::builder(18, 60).build())
+///         .build()
+/// })?;
+///
+/// std::thread::spawn(move || loop {
+///     controller.buffer(0)[0] = Led::RED;
+///     controller.submit().unwrap();
+/// });
+/// # Ok(()) }
+/// ```
+pub struct ThreadedController {
+    shared: Arc<Shared>,
+    /// The slot owned by this side of the triple buffer.
+    back: usize,
+    buffers: [Vec<Led>; NUM_CHANNELS],
+    brightness: [u8; NUM_CHANNELS],
+    min_frame_interval: Duration,
+    thread: Option<JoinHandle<Result<()>>>,
+    /// The sequence number of the most recently submitted frame.
+    submitted: usize,
+}
+
+struct Frame {
+    buffers: [Vec<Led>; NUM_CHANNELS],
+    brightness: [u8; NUM_CHANNELS],
+    sequence: usize,
+}
+
+struct Shared {
+    slots: [UnsafeCell<Frame>; 3],
+    /// Index of the slot currently owned by neither side, possibly combined with `FRESH`.
+    middle: AtomicUsize,
+    shutdown: AtomicBool,
+    progress: Mutex<Progress>,
+    /// Notified whenever `progress` changes.
+    progressed: Condvar,
+}
+
+/// How far the render thread has come, for [`ThreadedController::wait`].
+struct Progress {
+    /// The sequence number of the most recent frame that has been fully sent out.
+    completed: usize,
+    running: bool,
+}
+
+// SAFETY: Each slot is only accessed by the side currently owning it. Ownership of slots is
+// exchanged only through atomic swaps of `middle`, with acquire/release ordering.
+unsafe impl Sync for Shared {}
+
+impl Shared {
+    fn new(info: &DriverInfo) -> Self {
+        let new_frame = || {
+            UnsafeCell::new(Frame {
+                buffers: [
+                    vec![Led::OFF; info.channel_lens[0]],
+                    vec![Led::OFF; info.channel_lens[1]],
+                ],
+                brightness: info.brightness,
+                sequence: 0,
+            })
+        };
+        Self {
+            slots: [new_frame(), new_frame(), new_frame()],
+            middle: AtomicUsize::new(1),
+            shutdown: AtomicBool::new(false),
+            progress: Mutex::new(Progress {
+                completed: 0,
+                running: true,
+            }),
+            progressed: Condvar::new(),
+        }
+    }
+}
+
+impl ThreadedController {
+    /// Spawns the render thread and creates the driver on it by calling `init`.
+    /// Returns the error from `init`, if it fails.
+    pub fn spawn<D, F>(init: F) -> Result<Self>
+    where
+        D: LedDriver,
+        F: FnOnce() -> Result<D> + Send + 'static,
+    {
+        let (info_tx, info_rx) = mpsc::channel();
+        let thread = driver_thread::spawn(
+            init,
+            move |info| {
+                let info = info.map(|info| {
+                    let shared = Arc::new(Shared::new(&info));
+                    (info, shared)
+                });
+                let shared = info.as_ref().ok().map(|(_, shared)| shared.clone());
+                info_tx.send(info).ok().and(shared)
+            },
+            |mut driver, shared| render_thread(&mut driver, &shared),
+        );
+
+        match info_rx.recv() {
+            Ok(Ok((info, shared))) => Ok(Self {
+                shared,
+                back: 0,
+                buffers: [
+                    vec![Led::OFF; info.channel_lens[0]],
+                    vec![Led::OFF; info.channel_lens[1]],
+                ],
+                brightness: info.brightness,
+                min_frame_interval: info.min_frame_interval,
+                thread: Some(thread),
+                submitted: 0,
+            }),
+            Ok(Err(error)) => Err(error),
+            Err(mpsc::RecvError) => driver_thread::resume_panic(thread),
+        }
+    }
+
+    /// Returns a mutable slice where all the LED values can be set directly. The values are sent
+    /// to the render thread on the next call to [`ThreadedController::submit`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel_index >= NUM_CHANNELS`.
+    pub fn buffer(&mut self, channel_index: usize) -> &mut [Led] {
+        &mut self.buffers[channel_index]
+    }
+
+    /// Returns mutable slices where the LED values of all channels can be set directly.
+    pub fn buffers(&mut self) -> [&mut [Led]; NUM_CHANNELS] {
+        let [channel0, channel1] = &mut self.buffers;
+        [channel0, channel1]
+    }
+
+    /// Returns the number of LEDs on the channel with the given index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel_index >= NUM_CHANNELS`.
+    pub fn channel_len(&self, channel_index: usize) -> usize {
+        self.buffers[channel_index].len()
+    }
+
+    /// Returns the brightness of the channel with the given index, between 0 and 255.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel_index >= NUM_CHANNELS`.
+    pub fn brightness(&self, channel_index: usize) -> u8 {
+        self.brightness[channel_index]
+    }
+
+    /// Sets the brightness of the channel with the given index, between 0 and 255.
+    /// Is sent to the render thread together with the next submitted frame.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel_index >= NUM_CHANNELS`.
+    pub fn set_brightness(&mut self, channel_index: usize, brightness: u8) {
+        self.brightness[channel_index] = brightness;
+    }
+
+    /// Hands over a copy of what is currently in the buffers to the render thread, without
+    /// waiting for it to be rendered.
+    ///
+    /// Returns the error that made the render thread stop, if rendering has failed. After that,
+    /// all calls return [`Error::Generic`].
+    pub fn submit(&mut self) -> Result<()> {
+        self.check_thread()?;
+        // SAFETY: The back slot is exclusively owned by this side.
+        let frame = unsafe { &mut *self.shared.slots[self.back].get() };
+        for (slot, buffer) in frame.buffers.iter_mut().zip(&self.buffers) {
+            slot.copy_from_slice(buffer);
+        }
+        frame.brightness = self.brightness;
+        self.publish();
+        Ok(())
+    }
+
+    /// Hands over a copy of the given buffers to the render thread, without waiting for it to
+    /// be rendered. The buffers held by this instance are left untouched.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the `&[Led]` slices are not the same length as the corresponding channel.
+    pub fn submit_buffer(&mut self, buffers: [&[Led]; NUM_CHANNELS]) -> Result<()> {
+        assert_eq!(self.buffers[0].len(), buffers[0].len());
+        assert_eq!(self.buffers[1].len(), buffers[1].len());
+        self.check_thread()?;
+        // SAFETY: The back slot is exclusively owned by this side.
+        let frame = unsafe { &mut *self.shared.slots[self.back].get() };
+        for (slot, buffer) in frame.buffers.iter_mut().zip(&buffers) {
+            slot.copy_from_slice(buffer);
+        }
+        frame.brightness = self.brightness;
+        self.publish();
+        Ok(())
+    }
+
+    /// Blocks until the most recently submitted frame has been rendered and the driver on the
+    /// render thread has finished sending it out, see [`LedDriver::wait`]. Returns immediately
+    /// if nothing has been submitted. If a newer frame was submitted before an older one got
+    /// rendered, the older one is skipped and never waited for.
+    ///
+    /// Returns the error that made the render thread stop, like [`ThreadedController::submit`].
+    pub fn wait(&mut self) -> Result<()> {
+        let mut progress = self.shared.progress.lock().unwrap();
+        while progress.running && progress.completed != self.submitted {
+            progress = self.shared.progressed.wait(progress).unwrap();
+        }
+        let running = progress.running;
+        drop(progress);
+        if running {
+            Ok(())
+        } else {
+            self.join()
+        }
+    }
+
+    /// Numbers the frame in the back slot, swaps it into the middle and wakes up the render
+    /// thread.
+    fn publish(&mut self) {
+        self.submitted = self.submitted.wrapping_add(1);
+        // SAFETY: The back slot is exclusively owned by this side.
+        unsafe { (*self.shared.slots[self.back].get()).sequence = self.submitted };
+        let previous = self.shared.middle.swap(self.back | FRESH, Ordering::AcqRel);
+        self.back = previous & INDEX_MASK;
+        if let Some(thread) = &self.thread {
+            thread.thread().unpark();
+        }
+    }
+
+    fn check_thread(&mut self) -> Result<()> {
+        match &self.thread {
+            Some(thread) if thread.is_finished() => self.join(),
+            Some(_) => Ok(()),
+            None => Err(Error::Generic),
+        }
+    }
+
+    /// Waits for the render thread to exit, and returns the error that made it stop. Propagates
+    /// its panic.
+    fn join(&mut self) -> Result<()> {
+        match self.thread.take().map(JoinHandle::join) {
+            Some(Ok(result)) => result.and(Err(Error::Generic)),
+            Some(Err(panic)) => std::panic::resume_unwind(panic),
+            None => Err(Error::Generic),
+        }
+    }
+}
+
+/// Tells [`ThreadedController::wait`] when the render thread stops, also if it panics.
+struct StopGuard<'a>(&'a Shared);
+
+impl Drop for StopGuard<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut progress) = self.0.progress.lock() {
+            progress.running = false;
+        }
+        self.0.progressed.notify_all();
+    }
+}
+
+fn render_thread(driver: &mut impl LedDriver, shared: &Shared) -> Result<()> {
+    let _guard = StopGuard(shared);
+    let mut front = 2;
+    loop {
+        if shared.shutdown.load(Ordering::Acquire) {
+            return Ok(());
+        }
+        if shared.middle.load(Ordering::Acquire) & FRESH == 0 {
+            thread::park();
+            continue;
+        }
+        front = shared.middle.swap(front, Ordering::AcqRel) & INDEX_MASK;
+        // SAFETY: The front slot is exclusively owned by the render thread.
+        let frame = unsafe { &*shared.slots[front].get() };
+        for (channel_index, &brightness) in frame.brightness.iter().enumerate() {
+            driver.set_brightness(channel_index, brightness);
+        }
+        driver.render_buffer([&frame.buffers[0], &frame.buffers[1]])?;
+        driver.wait()?;
+        shared.progress.lock().unwrap().completed = frame.sequence;
+        shared.progressed.notify_all();
+    }
+}
+
+impl LedDriver for ThreadedController {
+    fn channel_len(&self, channel_index: usize) -> usize {
+        ThreadedController::channel_len(self, channel_index)
+    }
+
+    fn buffer(&mut self, channel_index: usize) -> &mut [Led] {
+        ThreadedController::buffer(self, channel_index)
+    }
+
+    fn buffers(&mut self) -> [&mut [Led]; NUM_CHANNELS] {
+        ThreadedController::buffers(self)
+    }
+
+    fn brightness(&self, channel_index: usize) -> u8 {
+        ThreadedController::brightness(self, channel_index)
+    }
+
+    fn set_brightness(&mut self, channel_index: usize, brightness: u8) {
+        ThreadedController::set_brightness(self, channel_index, brightness)
+    }
+
+    /// Same as [`ThreadedController::submit`].
+    fn render(&mut self) -> Result<()> {
+        self.submit()
+    }
+
+    /// Same as [`ThreadedController::submit_buffer`].
+    fn render_buffer(&mut self, buffers: [&[Led]; NUM_CHANNELS]) -> Result<()> {
+        self.submit_buffer(buffers)
+    }
+
+    fn min_frame_interval(&self) -> Duration {
+        self.min_frame_interval
+    }
+
+    /// Same as [`ThreadedController::wait`].
+    fn wait(&mut self) -> Result<()> {
+        ThreadedController::wait(self)
+    }
+}
+
+impl Drop for ThreadedController {
+    fn drop(&mut self) {
+        self.shared.shutdown.store(true, Ordering::Release);
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ThreadedController;
+    use crate::driver_thread::test_driver::Forward;
+    use crate::{Error, Led, LedDriver, SimulatedController};
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    fn spawn() -> (ThreadedController, mpsc::Receiver<(Vec<Led>, u8)>) {
+        let (tx, rx) = mpsc::channel();
+        let controller = ThreadedController::spawn(move || Ok(Forward::new([2, 0], tx))).unwrap();
+        (controller, rx)
+    }
+
+    #[test]
+    fn frames_are_rendered() {
+        let (mut controller, frames) = spawn();
+        assert_eq!(controller.channel_len(0), 2);
+        assert_eq!(controller.channel_len(1), 0);
+
+        controller.buffer(0)[1] = Led::RED;
+        controller.set_brightness(0, 20);
+        controller.submit().unwrap();
+        let timeout = Duration::from_secs(5);
+        assert_eq!(
+            frames.recv_timeout(timeout).unwrap(),
+            (vec![Led::OFF, Led::RED], 20)
+        );
+
+        controller.submit_buffer([&[Led::BLUE; 2], &[]]).unwrap();
+        assert_eq!(
+            frames.recv_timeout(timeout).unwrap(),
+            (vec![Led::BLUE, Led::BLUE], 20)
+        );
+        assert_eq!(controller.buffer(0), &[Led::OFF, Led::RED]);
+    }
+
+    #[test]
+    fn latest_frame_wins() {
+        let (mut controller, frames) = spawn();
+        for i in 0..100 {
+            controller.buffer(0)[0] = Led::new(0, i, 0, 0);
+            controller.submit().unwrap();
+        }
+        // Whatever frames were skipped, the last one is always rendered.
+        let mut last = None;
+        while let Ok((leds, _)) = frames.recv_timeout(Duration::from_millis(500)) {
+            last = Some(leds[0]);
+        }
+        assert_eq!(last, Some(Led::new(0, 99, 0, 0)));
+    }
+
+    #[test]
+    fn wait_for_rendered_frame() {
+        let (mut controller, frames) = spawn();
+        controller.wait().unwrap();
+        for i in 0..10 {
+            controller.buffer(0)[0] = Led::new(0, i, 0, 0);
+            controller.render_and_wait().unwrap();
+            // The frame has been rendered when waiting returns.
+            assert_eq!(frames.try_recv().unwrap().0[0], Led::new(0, i, 0, 0));
+        }
+        controller.buffer(0)[0] = Led::WHITE;
+        controller.submit().unwrap();
+        assert!(matches!(controller.wait(), Err(Error::SpiTransfer)));
+    }
+
+    #[test]
+    fn init_error_is_returned() {
+        let result = ThreadedController::spawn(|| Err::<SimulatedController, _>(Error::Mmap));
+        assert!(matches!(result, Err(Error::Mmap)));
+    }
+
+    #[test]
+    fn render_error_is_returned() {
+        let (mut controller, _frames) = spawn();
+        controller.buffer(0)[0] = Led::WHITE;
+        controller.submit().unwrap();
+        let error = loop {
+            if let Err(error) = controller.submit() {
+                break error;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        };
+        assert!(matches!(error, Error::SpiTransfer));
+        assert!(matches!(controller.submit(), Err(Error::Generic)));
+    }
+
+    #[test]
+    fn is_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<ThreadedController>();
+    }
+}
@@ -0,0 +1,299 @@
+use crate::effects::XorShift32;
+use crate::{Effect, Led};
+use std::fmt;
+use std::time::Duration;
+
+/// The part of the transition during which each LED fades over in
+/// [`TransitionCurve::Dissolve`].
+const DISSOLVE_FADE: f32 = 0.1;
+
+/// Ways of going from one frame to another. See [`Transition`] and [`TransitionCurve::blend`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TransitionCurve {
+    /// All LEDs crossfade at a constant rate.
+    Linear,
+    /// All LEDs crossfade, starting and ending slowly.
+    EaseInOut,
+    /// The new frame pushes the old one out, from the start of the strip to the end.
+    Wipe,
+    /// The LEDs switch to the new frame one by one, in a random order.
+    Dissolve,
+}
+
+impl TransitionCurve {
+    /// Blends the frames `from` and `to` into `out`, as they look at `progress` into the
+    /// transition. `progress` is clamped to `0.0..=1.0`, where 0.0 gives `from` and 1.0 gives
+    /// `to`. Colors are mixed in linear light, see [`Led::lerp_linear`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the three slices are not the same length.
+    pub fn blend(self, from: &[Led], to: &[Led], progress: f32, out: &mut [Led]) {
+        assert_eq!(from.len(), out.len());
+        assert_eq!(to.len(), out.len());
+        let progress = progress.clamp(0.0, 1.0);
+        let len = out.len();
+        let leds = from.iter().zip(to).zip(out).enumerate();
+        for (i, ((&from, &to), out)) in leds {
+            let t = match self {
+                TransitionCurve::Linear => progress,
+                TransitionCurve::EaseInOut => progress * progress * (3.0 - 2.0 * progress),
+                TransitionCurve::Wipe => progress * (len + 1) as f32 - i as f32,
+                TransitionCurve::Dissolve => {
+                    let start = dissolve_threshold(i) * (1.0 - DISSOLVE_FADE);
+                    (progress - start) / DISSOLVE_FADE
+                }
+            };
+            *out = Led::lerp_linear(from, to, t);
+        }
+    }
+}
+
+/// Returns a random, but for each index always the same, number in `0.0..1.0`.
+fn dissolve_threshold(index: usize) -> f32 {
+    let mut rng = XorShift32::new((index as u32).wrapping_mul(0x9e37_79b9) ^ 0x5bd1_e995);
+    rng.next_u32();
+    rng.next_f32()
+}
+
+/// An [`Effect`] that shows one effect at a time, and transitions smoothly to the next when
+/// switched with [`Transition::switch_to`], instead of cutting hard.
+///
+/// During the transition, both effects are rendered and blended with the configured
+/// [`TransitionCurve`]. Each effect gets the time since it was first shown as its `t`.
+///
+/// # Example
+///
+/// ```
+/// # use rpi_ws281x::effects::{Breathing, Rainbow};
+/// # use rpi_ws281x::{Effect, Led, Transition, TransitionCurve};
+/// use std::time::Duration;
+///
+/// let mut scene = Transition::new(Rainbow::new())
+///     .duration(Duration::from_secs(2))
+///     .curve(TransitionCurve::EaseInOut);
+/// let mut leds = vec![Led::OFF; 60];
+/// scene.render(Duration::from_secs(0), &mut leds);
+///
+/// // Fades over to the new effect during the two seconds after the next render.
+/// scene.switch_to(Breathing::new(Led::BLUE, Duration::from_secs(4)));
+/// scene.render(Duration::from_secs(10), &mut leds);
+/// assert!(scene.is_transitioning());
+/// scene.render(Duration::from_secs(12), &mut leds);
+/// assert!(!scene.is_transitioning());
+/// ```
+pub struct Transition {
+    current: Layer,
+    previous: Option<Layer>,
+    duration: Duration,
+    curve: TransitionCurve,
+    /// Holds the output of the previous effect during a transition.
+    from: Vec<Led>,
+    /// Holds the output of the current effect during a transition.
+    to: Vec<Led>,
+}
+
+/// An effect along with the time it was first rendered.
+struct Layer {
+    effect: Box<dyn Effect>,
+    start: Option<Duration>,
+}
+
+impl Layer {
+    fn new(effect: Box<dyn Effect>) -> Self {
+        Self {
+            effect,
+            start: None,
+        }
+    }
+
+    fn render(&mut self, t: Duration, leds: &mut [Led]) {
+        let start = *self.start.get_or_insert(t);
+        self.effect.render(t.saturating_sub(start), leds);
+    }
+}
+
+impl Transition {
+    /// Creates a transition layer showing the given effect.
+    pub fn new(effect: impl Effect + 'static) -> Self {
+        Self {
+            current: Layer::new(Box::new(effect)),
+            previous: None,
+            duration: Duration::from_secs(1),
+            curve: TransitionCurve::Linear,
+            from: Vec::new(),
+            to: Vec::new(),
+        }
+    }
+
+    /// Sets how long transitions take. Defaults to one second.
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Sets how transitions look. Defaults to [`TransitionCurve::Linear`].
+    pub fn curve(mut self, curve: TransitionCurve) -> Self {
+        self.curve = curve;
+        self
+    }
+
+    /// Starts a transition to the given effect. The transition starts on the next render.
+    ///
+    /// If a transition is already in progress, it keeps going underneath, and the new transition
+    /// fades out from what it shows. There is no hard cut.
+    pub fn switch_to(&mut self, effect: impl Effect + 'static) {
+        let next = Layer::new(Box::new(effect));
+        let current = std::mem::replace(&mut self.current, next);
+        let outgoing = match self.previous.take() {
+            None => current,
+            Some(previous) => Layer {
+                effect: Box::new(Transition {
+                    current,
+                    previous: Some(previous),
+                    duration: self.duration,
+                    curve: self.curve,
+                    from: std::mem::take(&mut self.from),
+                    to: std::mem::take(&mut self.to),
+                }),
+                // The layers of the transition in progress keep their own start times.
+                start: Some(Duration::ZERO),
+            },
+        };
+        self.previous = Some(outgoing);
+    }
+
+    /// Returns `true` while a transition is in progress.
+    pub fn is_transitioning(&self) -> bool {
+        self.previous.is_some()
+    }
+}
+
+impl Effect for Transition {
+    fn render(&mut self, t: Duration, leds: &mut [Led]) {
+        let previous = match &mut self.previous {
+            Some(previous) => previous,
+            None => return self.current.render(t, leds),
+        };
+        self.from.resize(leds.len(), Led::OFF);
+        self.to.resize(leds.len(), Led::OFF);
+        previous.render(t, &mut self.from);
+        self.current.render(t, &mut self.to);
+
+        let elapsed = t.saturating_sub(self.current.start.unwrap_or(t));
+        let progress = if self.duration.is_zero() {
+            1.0
+        } else {
+            (elapsed.as_secs_f64() / self.duration.as_secs_f64()) as f32
+        };
+        self.curve.blend(&self.from, &self.to, progress, leds);
+        if progress >= 1.0 {
+            self.previous = None;
+        }
+    }
+}
+
+impl fmt::Debug for Transition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Transition")
+            .field("duration", &self.duration)
+            .field("curve", &self.curve)
+            .field("is_transitioning", &self.is_transitioning())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Transition, TransitionCurve};
+    use crate::{Effect, Led};
+    use std::time::Duration;
+
+    /// Fills the strip with one color.
+    struct Solid(Led);
+
+    impl Effect for Solid {
+        fn render(&mut self, _t: Duration, leds: &mut [Led]) {
+            leds.fill(self.0);
+        }
+    }
+
+    fn blend(curve: TransitionCurve, progress: f32) -> Vec<Led> {
+        let mut out = vec![Led::OFF; 4];
+        curve.blend(&[Led::RED; 4], &[Led::BLUE; 4], progress, &mut out);
+        out
+    }
+
+    #[test]
+    fn curves() {
+        let half = Led::lerp_linear(Led::RED, Led::BLUE, 0.5);
+        assert_eq!(blend(TransitionCurve::Linear, 0.5), [half; 4]);
+        assert_eq!(blend(TransitionCurve::EaseInOut, 0.5), [half; 4]);
+        assert_eq!(
+            blend(TransitionCurve::EaseInOut, 0.25)[0],
+            Led::lerp_linear(Led::RED, Led::BLUE, 0.15625)
+        );
+        assert_eq!(
+            blend(TransitionCurve::Wipe, 0.5),
+            [Led::BLUE, Led::BLUE, half, Led::RED]
+        );
+        for curve in [
+            TransitionCurve::Linear,
+            TransitionCurve::EaseInOut,
+            TransitionCurve::Wipe,
+            TransitionCurve::Dissolve,
+        ] {
+            assert_eq!(blend(curve, 0.0), [Led::RED; 4], "{:?}", curve);
+            assert_eq!(blend(curve, 1.0), [Led::BLUE; 4], "{:?}", curve);
+        }
+    }
+
+    #[test]
+    fn dissolve() {
+        let mut out = vec![Led::OFF; 1000];
+        let to = vec![Led::BLUE; 1000];
+        TransitionCurve::Dissolve.blend(&[Led::RED; 1000], &to, 0.5, &mut out);
+        let switched = out.iter().filter(|&&led| led == Led::BLUE).count();
+        assert!((350..550).contains(&switched), "{}", switched);
+    }
+
+    #[test]
+    fn switches_effects() {
+        let mut transition = Transition::new(Solid(Led::RED)).duration(Duration::from_secs(2));
+        let mut leds = vec![Led::OFF; 3];
+        transition.render(Duration::from_secs(5), &mut leds);
+        assert_eq!(leds, [Led::RED; 3]);
+
+        transition.switch_to(Solid(Led::BLUE));
+        transition.render(Duration::from_secs(10), &mut leds);
+        assert_eq!(leds, [Led::RED; 3]);
+        transition.render(Duration::from_secs(11), &mut leds);
+        assert_eq!(leds, [Led::lerp_linear(Led::RED, Led::BLUE, 0.5); 3]);
+        assert!(transition.is_transitioning());
+        transition.render(Duration::from_secs(12), &mut leds);
+        assert_eq!(leds, [Led::BLUE; 3]);
+        assert!(!transition.is_transitioning());
+    }
+
+    #[test]
+    fn switches_during_transition() {
+        let mut transition = Transition::new(Solid(Led::RED)).duration(Duration::from_secs(2));
+        let mut leds = vec![Led::OFF; 3];
+        transition.render(Duration::from_secs(5), &mut leds);
+        transition.switch_to(Solid(Led::BLUE));
+        transition.render(Duration::from_secs(10), &mut leds);
+        transition.render(Duration::from_secs(11), &mut leds);
+        let half = Led::lerp_linear(Led::RED, Led::BLUE, 0.5);
+        assert_eq!(leds, [half; 3]);
+
+        // Continues from the blend of the interrupted transition.
+        transition.switch_to(Solid(Led::GREEN));
+        transition.render(Duration::from_secs(11), &mut leds);
+        assert_eq!(leds, [half; 3]);
+        transition.render(Duration::from_secs(12), &mut leds);
+        assert_eq!(leds, [Led::lerp_linear(Led::BLUE, Led::GREEN, 0.5); 3]);
+        transition.render(Duration::from_secs(13), &mut leds);
+        assert_eq!(leds, [Led::GREEN; 3]);
+        assert!(!transition.is_transitioning());
+    }
+}
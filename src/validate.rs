@@ -0,0 +1,405 @@
+use crate::{sys, NUM_CHANNELS};
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::os::raw::c_int;
+
+/// Pins connected to the first PWM block. Can only be used by channel 0.
+const PWM0_PINS: &[u8] = &[12, 18, 40, 52];
+/// Pins connected to the second PWM block. Can only be used by channel 1.
+const PWM1_PINS: &[u8] = &[13, 19, 41, 45, 53];
+/// Pins driven by the PCM hardware. Can only be used by channel 0.
+const PCM_PINS: &[u8] = &[21, 31];
+/// The SPI MOSI pin. Can only be used by channel 0.
+const SPI_PINS: &[u8] = &[10];
+/// Every pin that channel 0 can use.
+const CHANNEL0_PINS: &[u8] = &[12, 18, 40, 52, 21, 31, 10];
+
+/// DMA channels that exist and can be selected.
+const DMA_CHANNELS: RangeInclusive<u8> = 0..=14;
+/// DMA channels known to be used by the firmware or the kernel on some systems. Using them can
+/// interfere with other hardware, and for example corrupt the SD card.
+const DANGEROUS_DMA_CHANNELS: &[u8] = &[0, 1, 2, 3, 5, 6, 7];
+/// A DMA channel that is free on most systems.
+const RECOMMENDED_DMA_CHANNEL: u8 = 10;
+
+/// The range of frequencies ws281x type LEDs can be driven at, in Hz.
+const FREQUENCIES: RangeInclusive<u32> = 400_000..=800_000;
+
+/// A problem with a controller configuration, found before touching any hardware.
+/// See [`ControllerBuilder::validate`](crate::ControllerBuilder::validate).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ConfigError {
+    /// The GPIO pin can not be driven by the hardware behind the channel.
+    IllegalGpio {
+        /// Index of the offending channel.
+        channel_index: usize,
+        /// The configured GPIO pin.
+        gpio_pin: u8,
+        /// The pins that can be used on this channel.
+        valid_pins: &'static [u8],
+    },
+    /// Both channels are configured to use pins connected to the same PWM block. Every PWM
+    /// block can only drive one strip.
+    SharedPwmBlock {
+        /// The GPIO pins of channel 0 and 1.
+        gpio_pins: [u8; NUM_CHANNELS],
+    },
+    /// Channel 0 uses PCM or SPI, which can only drive a single strip, but channel 1 is
+    /// enabled too.
+    SecondChannelUnsupported {
+        /// The GPIO pin of channel 0.
+        gpio_pin: u8,
+    },
+    /// The DMA channel does not exist.
+    InvalidDma(u8),
+    /// The DMA channel is commonly used by other parts of the system. Can be allowed with
+    /// [`ControllerBuilder::allow_dangerous_dma`](crate::ControllerBuilder::allow_dangerous_dma).
+    DangerousDma(u8),
+    /// The frequency is outside of what ws281x type LEDs support.
+    InvalidFrequency(u32),
+    /// The channel has more LEDs than the C library can represent. For a chain of strips, this
+    /// is the number of LEDs after repacking.
+    TooManyLeds {
+        /// Index of the offending channel.
+        channel_index: usize,
+        /// The number of LEDs the C library would have to send.
+        led_count: u64,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::IllegalGpio {
+                channel_index,
+                gpio_pin,
+                valid_pins,
+            } => {
+                write!(
+                    f,
+                    "GPIO {} can not be used on channel {}, valid pins are ",
+                    gpio_pin, channel_index
+                )?;
+                write_list(f, valid_pins)
+            }
+            ConfigError::SharedPwmBlock { gpio_pins } => {
+                write!(
+                    f,
+                    "GPIO {} and {} are both connected to the same PWM block, use one of ",
+                    gpio_pins[0], gpio_pins[1]
+                )?;
+                write_list(f, PWM0_PINS)?;
+                f.write_str(" on channel 0 and one of ")?;
+                write_list(f, PWM1_PINS)?;
+                f.write_str(" on channel 1")
+            }
+            ConfigError::SecondChannelUnsupported { gpio_pin } => {
+                write!(
+                    f,
+                    "GPIO {} only supports a single channel, disable channel 1 or use one of ",
+                    gpio_pin
+                )?;
+                write_list(f, PWM0_PINS)?;
+                f.write_str(" on channel 0")
+            }
+            ConfigError::InvalidDma(dma_channel) => write!(
+                f,
+                "DMA channel {} does not exist, valid channels are {} to {}",
+                dma_channel,
+                DMA_CHANNELS.start(),
+                DMA_CHANNELS.end()
+            ),
+            ConfigError::DangerousDma(dma_channel) => write!(
+                f,
+                "DMA channel {} is commonly used by the system and might corrupt the SD card, \
+                 {} is free on most systems",
+                dma_channel, RECOMMENDED_DMA_CHANNEL
+            ),
+            ConfigError::InvalidFrequency(freq) => write!(
+                f,
+                "{} Hz is not a valid frequency, use between {} and {} Hz",
+                freq,
+                FREQUENCIES.start(),
+                FREQUENCIES.end()
+            ),
+            ConfigError::TooManyLeds {
+                channel_index,
+                led_count,
+            } => write!(
+                f,
+                "channel {} would send {} LEDs, the C library supports at most {}",
+                channel_index,
+                led_count,
+                c_int::MAX
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+fn write_list(f: &mut fmt::Formatter<'_>, values: &[u8]) -> fmt::Result {
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
+        }
+        write!(f, "{}", value)?;
+    }
+    Ok(())
+}
+
+/// Checks the configuration against what the hardware supports, without calling into C.
+/// `raw_lens` are the numbers of LEDs the C library has to send on each channel.
+pub(crate) fn validate(
+    raw: &sys::ws2811_t,
+    raw_lens: [u64; NUM_CHANNELS],
+    allow_dangerous_dma: bool,
+) -> Result<(), ConfigError> {
+    let dma_channel = u8::try_from(raw.dmanum).unwrap_or(u8::MAX);
+    if !DMA_CHANNELS.contains(&dma_channel) {
+        return Err(ConfigError::InvalidDma(dma_channel));
+    }
+    if !allow_dangerous_dma && DANGEROUS_DMA_CHANNELS.contains(&dma_channel) {
+        return Err(ConfigError::DangerousDma(dma_channel));
+    }
+    if !FREQUENCIES.contains(&raw.freq) {
+        return Err(ConfigError::InvalidFrequency(raw.freq));
+    }
+    for (channel_index, &led_count) in raw_lens.iter().enumerate() {
+        if c_int::try_from(led_count).is_err() {
+            return Err(ConfigError::TooManyLeds {
+                channel_index,
+                led_count,
+            });
+        }
+    }
+
+    // Pin 0 means the channel is disabled.
+    let gpio_pins = [gpio_pin(&raw.channel[0]), gpio_pin(&raw.channel[1])];
+    if PCM_PINS.contains(&gpio_pins[0]) || SPI_PINS.contains(&gpio_pins[0]) {
+        if gpio_pins[1] != 0 {
+            return Err(ConfigError::SecondChannelUnsupported {
+                gpio_pin: gpio_pins[0],
+            });
+        }
+        return Ok(());
+    }
+    let both_on = |block: &[u8]| block.contains(&gpio_pins[0]) && block.contains(&gpio_pins[1]);
+    if both_on(PWM0_PINS) || both_on(PWM1_PINS) {
+        return Err(ConfigError::SharedPwmBlock { gpio_pins });
+    }
+    let valid_pins = [CHANNEL0_PINS, PWM1_PINS];
+    for (channel_index, (&gpio_pin, &valid_pins)) in gpio_pins.iter().zip(&valid_pins).enumerate() {
+        if gpio_pin != 0 && !valid_pins.contains(&gpio_pin) {
+            return Err(ConfigError::IllegalGpio {
+                channel_index,
+                gpio_pin,
+                valid_pins,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Checks the configuration for the pure Rust SPI backend. Only channel 0 can be used, on the
+/// SPI pin. The DMA channel is not used.
+pub(crate) fn validate_spi(raw: &sys::ws2811_t) -> Result<(), ConfigError> {
+    if !FREQUENCIES.contains(&raw.freq) {
+        return Err(ConfigError::InvalidFrequency(raw.freq));
+    }
+    let gpio_pins = [gpio_pin(&raw.channel[0]), gpio_pin(&raw.channel[1])];
+    if !SPI_PINS.contains(&gpio_pins[0]) {
+        return Err(ConfigError::IllegalGpio {
+            channel_index: 0,
+            gpio_pin: gpio_pins[0],
+            valid_pins: SPI_PINS,
+        });
+    }
+    if gpio_pins[1] != 0 {
+        return Err(ConfigError::SecondChannelUnsupported {
+            gpio_pin: gpio_pins[0],
+        });
+    }
+    Ok(())
+}
+
+fn gpio_pin(channel: &sys::ws2811_channel_t) -> u8 {
+    u8::try_from(channel.gpionum).unwrap_or(u8::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ConfigError;
+    use crate::{Channel, Controller, ControllerBuilder, Strip, StripType};
+
+    fn channels(pin0: u8, pin1: u8) -> ControllerBuilder {
+        let channel = |pin| match pin {
+            0 => Channel::disabled(),
+            pin => Channel::builder(pin, 10).build(),
+        };
+        Controller::builder(10).channels([channel(pin0), channel(pin1)])
+    }
+
+    #[test]
+    fn valid_configs() {
+        assert_eq!(channels(18, 0).validate(), Ok(()));
+        assert_eq!(channels(12, 13).validate(), Ok(()));
+        assert_eq!(channels(18, 19).validate(), Ok(()));
+        assert_eq!(channels(0, 19).validate(), Ok(()));
+        assert_eq!(channels(21, 0).validate(), Ok(()));
+        assert_eq!(channels(10, 0).validate(), Ok(()));
+        assert_eq!(channels(0, 0).validate(), Ok(()));
+    }
+
+    #[test]
+    fn illegal_gpio() {
+        let error = channels(17, 0).validate().unwrap_err();
+        assert_eq!(
+            error,
+            ConfigError::IllegalGpio {
+                channel_index: 0,
+                gpio_pin: 17,
+                valid_pins: &[12, 18, 40, 52, 21, 31, 10],
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "GPIO 17 can not be used on channel 0, valid pins are 12, 18, 40, 52, 21, 31, 10"
+        );
+        assert!(matches!(
+            channels(13, 0).validate(),
+            Err(ConfigError::IllegalGpio {
+                channel_index: 0,
+                gpio_pin: 13,
+                ..
+            })
+        ));
+        assert!(matches!(
+            channels(18, 10).validate(),
+            Err(ConfigError::IllegalGpio {
+                channel_index: 1,
+                gpio_pin: 10,
+                valid_pins: &[13, 19, 41, 45, 53],
+            })
+        ));
+    }
+
+    #[test]
+    fn shared_pwm_block() {
+        assert_eq!(
+            channels(18, 12).validate(),
+            Err(ConfigError::SharedPwmBlock {
+                gpio_pins: [18, 12]
+            })
+        );
+        assert_eq!(
+            channels(13, 19).validate(),
+            Err(ConfigError::SharedPwmBlock {
+                gpio_pins: [13, 19]
+            })
+        );
+    }
+
+    #[test]
+    fn single_channel_hardware() {
+        assert_eq!(
+            channels(10, 13).validate(),
+            Err(ConfigError::SecondChannelUnsupported { gpio_pin: 10 })
+        );
+        assert_eq!(
+            channels(21, 19).validate(),
+            Err(ConfigError::SecondChannelUnsupported { gpio_pin: 21 })
+        );
+    }
+
+    #[test]
+    fn dma() {
+        let builder = |dma| Controller::builder(dma).channel(Channel::builder(18, 1).build());
+        assert_eq!(builder(15).validate(), Err(ConfigError::InvalidDma(15)));
+        assert_eq!(builder(5).validate(), Err(ConfigError::DangerousDma(5)));
+        assert_eq!(builder(5).allow_dangerous_dma().validate(), Ok(()));
+        assert_eq!(
+            builder(200).allow_dangerous_dma().validate(),
+            Err(ConfigError::InvalidDma(200))
+        );
+        assert_eq!(builder(14).validate(), Ok(()));
+    }
+
+    #[test]
+    fn frequency() {
+        let builder = |freq| channels(18, 0).freq(freq);
+        assert_eq!(builder(400_000).validate(), Ok(()));
+        assert_eq!(builder(0).validate(), Err(ConfigError::InvalidFrequency(0)));
+        assert_eq!(
+            builder(1_000_000).validate(),
+            Err(ConfigError::InvalidFrequency(1_000_000))
+        );
+    }
+
+    #[test]
+    fn led_count() {
+        let builder = |channel| Controller::builder(10).channel(channel);
+        let max = i32::MAX as u32;
+        assert_eq!(
+            builder(Channel::builder(18, max).build()).validate(),
+            Ok(())
+        );
+        let error = builder(Channel::builder(18, max + 1).build())
+            .validate()
+            .unwrap_err();
+        assert_eq!(
+            error,
+            ConfigError::TooManyLeds {
+                channel_index: 0,
+                led_count: u64::from(max) + 1,
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "channel 0 would send 2147483648 LEDs, the C library supports at most 2147483647"
+        );
+
+        // Four bytes per LED are repacked into 4/3 as many LEDs.
+        let strips = Channel::builder(18, 0)
+            .strips([Strip::new(max, StripType::Grbw)])
+            .build();
+        assert!(matches!(
+            builder(strips).validate(),
+            Err(ConfigError::TooManyLeds {
+                channel_index: 0,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn spi() {
+        assert_eq!(super::validate_spi(&channels(10, 0).raw), Ok(()));
+        assert_eq!(
+            super::validate_spi(&channels(18, 0).raw),
+            Err(ConfigError::IllegalGpio {
+                channel_index: 0,
+                gpio_pin: 18,
+                valid_pins: &[10],
+            })
+        );
+        assert_eq!(
+            super::validate_spi(&channels(10, 13).raw),
+            Err(ConfigError::SecondChannelUnsupported { gpio_pin: 10 })
+        );
+        // The DMA channel does not matter for SPI.
+        let builder = Controller::builder(5).channel(Channel::builder(10, 1).build());
+        assert_eq!(super::validate_spi(&builder.raw), Ok(()));
+    }
+
+    #[test]
+    fn build_validates() {
+        let result = channels(17, 0).build();
+        assert!(matches!(
+            result,
+            Err(crate::Error::Config(ConfigError::IllegalGpio { .. }))
+        ));
+    }
+}
@@ -0,0 +1,326 @@
+use crate::{Led, LedDriver, NUM_CHANNELS};
+use std::ops::{Index, IndexMut, Range};
+
+/// A range of LEDs on one channel, forming a part of a [`VirtualStrip`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Segment {
+    channel_index: usize,
+    range: Range<usize>,
+    reversed: bool,
+}
+
+impl Segment {
+    /// Creates a segment covering the given range of LEDs on the channel with the given index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channel_index >= NUM_CHANNELS` or if the range ends before it starts.
+    pub fn new(channel_index: usize, range: Range<usize>) -> Self {
+        assert!(channel_index < NUM_CHANNELS, "channel index out of bounds");
+        assert!(
+            range.start <= range.end,
+            "segment range ends before it starts"
+        );
+        Self {
+            channel_index,
+            range,
+            reversed: false,
+        }
+    }
+
+    /// Sets if the segment runs backwards, so the first logical LED of the segment is the last
+    /// LED of the range on the channel. Defaults to `false`.
+    pub fn reverse(mut self, reversed: bool) -> Self {
+        self.reversed = reversed;
+        self
+    }
+
+    /// Returns the index of the channel the segment is on.
+    pub fn channel_index(&self) -> usize {
+        self.channel_index
+    }
+
+    /// Returns the range of LEDs on the channel covered by the segment.
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// Returns `true` if the segment runs backwards.
+    pub fn is_reversed(&self) -> bool {
+        self.reversed
+    }
+
+    /// Returns the number of LEDs in the segment.
+    pub fn len(&self) -> usize {
+        self.range.len()
+    }
+
+    /// Returns `true` if the segment covers no LEDs.
+    pub fn is_empty(&self) -> bool {
+        self.range.is_empty()
+    }
+
+    /// Returns the index into the channel buffer of the LED at `offset` into the segment.
+    fn channel_offset(&self, offset: usize) -> usize {
+        if self.reversed {
+            self.range.end - 1 - offset
+        } else {
+            self.range.start + offset
+        }
+    }
+}
+
+/// A logical strip made up of any number of [`Segment`]s, on any of the channels. Lets one
+/// animation flow across strips connected to different GPIO pins, without knowing which
+/// physical channel each LED is on.
+///
+/// The virtual strip only describes the mapping. To read and write LEDs, get a
+/// [`VirtualStripView`] over the buffers of a driver with [`VirtualStrip::view`].
+///
+/// # Example
+///
+/// ```
+/// # use rpi_ws281x::{Channel, Controller, Led, LedDriver, Segment, VirtualStrip};
+/// let mut controller = Controller::builder(10)
+///     .channels([
+///         Channel::builder(18, 10).build(),
+///         Channel::builder(13, 10).build(),
+///     ])
+///     .build_simulated();
+/// // The strip on channel 1 is mounted upside down, and continues where channel 0 ends.
+/// let strip = VirtualStrip::new([Segment::new(0, 0..10), Segment::new(1, 0..10).reverse(true)]);
+///
+/// let mut view = strip.view(&mut controller);
+/// view[10] = Led::RED;
+/// assert_eq!(controller.buffer(1)[9], Led::RED);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VirtualStrip {
+    segments: Vec<Segment>,
+    len: usize,
+}
+
+impl VirtualStrip {
+    /// Creates a virtual strip from the given segments, in logical order. Segments are allowed
+    /// to overlap, in which case writes to either of them end up on the same LEDs.
+    pub fn new(segments: impl IntoIterator<Item = Segment>) -> Self {
+        let segments: Vec<Segment> = segments.into_iter().collect();
+        let len = segments.iter().map(Segment::len).sum();
+        Self { segments, len }
+    }
+
+    /// Returns the segments making up the strip.
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Returns the total number of LEDs in all segments.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the strip has no LEDs.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns a view over the buffers of the given driver, addressed by the logical indices of
+    /// this strip.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any segment reaches past the end of its channel.
+    pub fn view<'a, D: LedDriver + ?Sized>(&'a self, driver: &'a mut D) -> VirtualStripView<'a> {
+        self.view_buffers(driver.buffers())
+    }
+
+    /// Returns a view over the given channel buffers, addressed by the logical indices of this
+    /// strip. Works with [`LedDriver::buffers`] as well as with buffers that are later passed
+    /// to [`LedDriver::render_buffer`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if any segment reaches past the end of its channel.
+    pub fn view_buffers<'a>(
+        &'a self,
+        buffers: [&'a mut [Led]; NUM_CHANNELS],
+    ) -> VirtualStripView<'a> {
+        for segment in &self.segments {
+            assert!(
+                segment.range.end <= buffers[segment.channel_index].len(),
+                "segment {:?} out of bounds for channel {} with {} LEDs",
+                segment.range,
+                segment.channel_index,
+                buffers[segment.channel_index].len()
+            );
+        }
+        VirtualStripView {
+            strip: self,
+            buffers,
+        }
+    }
+
+    /// Returns the channel index and the index into the channel buffer of the LED with the
+    /// given logical index.
+    fn locate(&self, mut index: usize) -> Option<(usize, usize)> {
+        for segment in &self.segments {
+            if index < segment.len() {
+                return Some((segment.channel_index, segment.channel_offset(index)));
+            }
+            index -= segment.len();
+        }
+        None
+    }
+}
+
+/// Mutable access to the LEDs of a [`VirtualStrip`], created with [`VirtualStrip::view`].
+///
+/// Can be indexed like a slice, with `view[i]`.
+#[derive(Debug)]
+pub struct VirtualStripView<'a> {
+    strip: &'a VirtualStrip,
+    buffers: [&'a mut [Led]; NUM_CHANNELS],
+}
+
+impl VirtualStripView<'_> {
+    /// Returns the number of LEDs in the view.
+    pub fn len(&self) -> usize {
+        self.strip.len
+    }
+
+    /// Returns `true` if the view has no LEDs.
+    pub fn is_empty(&self) -> bool {
+        self.strip.is_empty()
+    }
+
+    /// Returns the LED with the given logical index, or `None` if it is out of bounds.
+    pub fn get(&self, index: usize) -> Option<Led> {
+        let (channel_index, offset) = self.strip.locate(index)?;
+        Some(self.buffers[channel_index][offset])
+    }
+
+    /// Returns a mutable reference to the LED with the given logical index, or `None` if it is
+    /// out of bounds.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut Led> {
+        let (channel_index, offset) = self.strip.locate(index)?;
+        Some(&mut self.buffers[channel_index][offset])
+    }
+
+    /// Returns an iterator over the LEDs in logical order.
+    pub fn iter(&self) -> impl Iterator<Item = Led> + '_ {
+        self.strip.segments.iter().flat_map(move |segment| {
+            let buffer = &self.buffers[segment.channel_index];
+            (0..segment.len()).map(move |offset| buffer[segment.channel_offset(offset)])
+        })
+    }
+
+    /// Sets all LEDs in the view to the given color.
+    pub fn fill(&mut self, led: Led) {
+        for segment in &self.strip.segments {
+            self.buffers[segment.channel_index][segment.range.clone()].fill(led);
+        }
+    }
+
+    /// Copies the given LEDs into the view, in logical order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `leds` is not the same length as the view.
+    pub fn copy_from_slice(&mut self, leds: &[Led]) {
+        assert_eq!(self.len(), leds.len());
+        let mut leds = leds;
+        for segment in &self.strip.segments {
+            let (head, tail) = leds.split_at(segment.len());
+            let buffer = &mut self.buffers[segment.channel_index][segment.range.clone()];
+            if segment.reversed {
+                for (dst, &src) in buffer.iter_mut().rev().zip(head) {
+                    *dst = src;
+                }
+            } else {
+                buffer.copy_from_slice(head);
+            }
+            leds = tail;
+        }
+    }
+}
+
+impl Index<usize> for VirtualStripView<'_> {
+    type Output = Led;
+
+    fn index(&self, index: usize) -> &Led {
+        let (channel_index, offset) = self
+            .strip
+            .locate(index)
+            .unwrap_or_else(|| panic!("index {} out of bounds for {} LEDs", index, self.len()));
+        &self.buffers[channel_index][offset]
+    }
+}
+
+impl IndexMut<usize> for VirtualStripView<'_> {
+    fn index_mut(&mut self, index: usize) -> &mut Led {
+        let len = self.len();
+        let (channel_index, offset) = self
+            .strip
+            .locate(index)
+            .unwrap_or_else(|| panic!("index {} out of bounds for {} LEDs", index, len));
+        &mut self.buffers[channel_index][offset]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Segment, VirtualStrip};
+    use crate::Led;
+
+    fn led(value: u8) -> Led {
+        Led::new(0, value, 0, 0)
+    }
+
+    #[test]
+    fn maps_across_channels() {
+        let strip = VirtualStrip::new([
+            Segment::new(0, 1..3),
+            Segment::new(1, 0..3).reverse(true),
+            Segment::new(0, 3..4),
+        ]);
+        assert_eq!(strip.len(), 6);
+
+        let mut channel0 = [Led::OFF; 4];
+        let mut channel1 = [Led::OFF; 3];
+        let mut view = strip.view_buffers([&mut channel0, &mut channel1]);
+        for i in 0..view.len() {
+            view[i] = led(i as u8 + 1);
+        }
+        assert_eq!(view.get(6), None);
+        assert_eq!(
+            view.iter().collect::<Vec<_>>(),
+            (1..=6).map(led).collect::<Vec<_>>()
+        );
+        assert_eq!(channel0, [Led::OFF, led(1), led(2), led(6)]);
+        assert_eq!(channel1, [led(5), led(4), led(3)]);
+    }
+
+    #[test]
+    fn copy_and_fill() {
+        let strip = VirtualStrip::new([Segment::new(1, 0..2).reverse(true), Segment::new(0, 0..1)]);
+        let mut channel0 = [Led::OFF; 2];
+        let mut channel1 = [Led::OFF; 2];
+
+        let mut view = strip.view_buffers([&mut channel0, &mut channel1]);
+        view.copy_from_slice(&[led(1), led(2), led(3)]);
+        assert_eq!(channel0, [led(3), Led::OFF]);
+        assert_eq!(channel1, [led(2), led(1)]);
+
+        let mut view = strip.view_buffers([&mut channel0, &mut channel1]);
+        view.fill(Led::WHITE);
+        assert_eq!(channel0, [Led::WHITE, Led::OFF]);
+        assert_eq!(channel1, [Led::WHITE; 2]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn segment_out_of_bounds() {
+        let strip = VirtualStrip::new([Segment::new(0, 0..3)]);
+        strip.view_buffers([&mut [Led::OFF; 2], &mut []]);
+    }
+}
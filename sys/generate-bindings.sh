@@ -17,7 +17,7 @@ bindgen \
     --raw-line "// Generated against rpi_ws281x $RPI_WS281X_VERSION" \
     --raw-line "" \
     --raw-line "#![allow(non_camel_case_types, dead_code)]" \
-    --whitelist-function "ws2811_(init|render|fini|get_return_t_str)" \
+    --whitelist-function "ws2811_(init|render|wait|fini|get_return_t_str|set_custom_gamma_factor)" \
     --whitelist-var "(WS2811|SK6812)_STRIP_.*" \
     --whitelist-var "WS2811_TARGET_FREQ" \
     --whitelist-var "RPI_PWM_CHANNELS" \
@@ -90,6 +90,12 @@ extern "C" {
 extern "C" {
     pub fn ws2811_render(ws2811: *mut ws2811_t) -> ws2811_return_t;
 }
+extern "C" {
+    pub fn ws2811_wait(ws2811: *mut ws2811_t) -> ws2811_return_t;
+}
 extern "C" {
     pub fn ws2811_get_return_t_str(state: ws2811_return_t) -> *const ::std::os::raw::c_char;
 }
+extern "C" {
+    pub fn ws2811_set_custom_gamma_factor(ws2811: *mut ws2811_t, gamma_factor: f64);
+}